@@ -1,23 +1,40 @@
-use std::collections::hash_map::Entry::Vacant;
 use std::thread;
-use std::{collections::HashMap, path::PathBuf, sync::Arc};
+use std::{
+    collections::{HashMap, HashSet},
+    path::PathBuf,
+    sync::{Arc, Mutex},
+    time::Instant,
+};
 
-use anyhow::Result;
+use anyhow::{Result, anyhow};
+use chrono::NaiveDate;
 use tokio::runtime::Handle;
 use tokio::{
     fs::File,
     io::AsyncWriteExt,
     runtime::Builder,
     select, spawn,
-    sync::mpsc::{Receiver, Sender, channel},
+    sync::{
+        Semaphore,
+        mpsc::{Receiver, Sender, channel},
+    },
 };
 use tokio_util::sync::CancellationToken;
 
-use crate::fetcher::build_fetcher;
-use crate::{Sites, Strip};
+use crate::cache::{ImageCache, ImageData, IndexCache};
+use crate::export::{ExportFormat, ExportRange, ExportReport, export};
+use crate::fetcher::{build_cached_fetcher, build_local_fetcher, subscribe_feed};
+use crate::mirror::site_mirror_dir;
+use crate::{FetcherErrors, Sites, Strip};
 
 type Fetcher = Arc<dyn crate::Fetcher + Send + Sync + 'static>;
 
+/// Neighbouring strips resolved at the same time, at most
+const PREFETCH_CONCURRENCY: usize = 2;
+
+/// Resolved strips kept around before starting over
+const PREFETCH_CAPACITY: usize = 128;
+
 #[derive(Clone, Copy, Debug, Default, Hash, PartialEq, Eq)]
 pub enum RequestStripType {
     Last,
@@ -25,18 +42,63 @@ pub enum RequestStripType {
     Random,
     Next(Option<usize>),
     Prev(Option<usize>),
+    /// Strip published closest to the date
+    ByDate(NaiveDate),
 }
 
 #[derive(Clone, Hash, PartialEq, Eq)]
 pub enum Request {
-    Strip { site: Sites, ty: RequestStripType },
-    Download { path: PathBuf, url: String },
+    Strip {
+        site: Sites,
+        ty: RequestStripType,
+    },
+    Download {
+        path: PathBuf,
+        url: String,
+    },
+    /// Bytes of the image at `url`, served from the shared image cache
+    Image {
+        url: String,
+    },
+    /// Write the strips of `range` to `path`, answered by `ExportProgress` updates and `Export`
+    Export {
+        site: Sites,
+        range: ExportRange,
+        format: ExportFormat,
+        path: PathBuf,
+    },
+    /// Browse `site` from its mirror rather than from the network
+    Offline {
+        site: Sites,
+        enabled: bool,
+    },
+    /// Follow the RSS or Atom feed at `url` as a new site, answered by `Subscribed`
+    Subscribe {
+        url: String,
+    },
 }
 
 #[derive(Debug)]
 pub enum Response {
-    Strip(Option<Strip>),
+    /// Answer to `Request::Strip`, telling which one so that stale answers can be ignored
+    Strip {
+        site: Sites,
+        ty: RequestStripType,
+        strip: Result<Strip, FetcherErrors>,
+    },
     Download(Result<()>),
+    Image {
+        url: String,
+        data: Result<ImageData>,
+    },
+    ExportProgress {
+        done: usize,
+        total: usize,
+    },
+    /// Strips exported and left out
+    Export(Result<ExportReport>),
+    /// Site following the feed, to be listed along with the others
+    Subscribed(Result<Sites, FetcherErrors>),
 }
 
 pub fn start_backend() -> (Handle, Sender<Request>, Receiver<Response>) {
@@ -57,56 +119,267 @@ pub fn start_backend() -> (Handle, Sender<Request>, Receiver<Response>) {
 }
 
 async fn background_task(mut rx: Receiver<Request>, tx: Sender<Response>) {
-    let mut fetchers: HashMap<Sites, Fetcher> = HashMap::default();
+    let mut fetchers: HashMap<Sites, (Fetcher, Instant)> = HashMap::default();
+    let mut offline = HashSet::new();
     let mut cancel_token = None;
+    let index_cache = IndexCache::default();
+    let image_cache = Arc::new(ImageCache::default());
+    let prefetcher = Prefetcher::new(image_cache.clone());
 
     while let Some(req) = rx.recv().await {
         match req {
             Request::Strip { site, ty } => {
-                if let Vacant(e) = fetchers.entry(site)
-                    && let Some(val) = build_fetcher(site).await.map(|f| Arc::new(f) as Fetcher)
-                {
-                    e.insert(val);
-                }
-
                 let tx = tx.clone();
+                let prefetcher = prefetcher.clone();
 
-                let fetcher = fetchers.get(&site).unwrap().clone();
                 let actual_cancel_token = CancellationToken::new();
                 if let Some(prev_token) = cancel_token.replace(actual_cancel_token.clone()) {
                     prev_token.cancel();
                 }
+                let fetcher = get_fetcher(&mut fetchers, site, &offline, &index_cache, &prefetcher);
+                let fetcher = match fetcher.await {
+                    Ok(fetcher) => fetcher,
+                    Err(err) => {
+                        let strip = Err(err);
+                        let _ = tx.send(Response::Strip { site, ty, strip }).await;
+                        continue;
+                    }
+                };
                 spawn(async move {
                     select! {
                         _ = actual_cancel_token.cancelled() => {}
-                        content = get_content_background(ty, fetcher) => {
-                            let _ = tx.send(Response::Strip(content)).await;
-
+                        strip = get_content_background(site, ty, fetcher.clone(), &prefetcher) => {
+                            if let Ok(strip) = strip.as_ref() {
+                                prefetcher.prefetch_neighbours(strip, fetcher);
+                            }
+                            let _ = tx.send(Response::Strip { site, ty, strip }).await;
                         }
                     }
                 });
             }
             Request::Download { path, url } => {
-                let res = download_background(path, url).await;
+                let res = download_background(path, url, &image_cache).await;
                 let _ = tx.send(Response::Download(res)).await;
             }
+            Request::Image { url } => {
+                let tx = tx.clone();
+                let image_cache = image_cache.clone();
+                spawn(async move {
+                    let data = image_cache.get(&url).await;
+                    let _ = tx.send(Response::Image { url, data }).await;
+                });
+            }
+            Request::Export {
+                site,
+                range,
+                format,
+                path,
+            } => {
+                let fetcher = get_fetcher(&mut fetchers, site, &offline, &index_cache, &prefetcher);
+                let fetcher = match fetcher.await {
+                    Ok(fetcher) => fetcher,
+                    Err(err) => {
+                        let err = anyhow!("failed to load the archive of {site}: {err}");
+                        let _ = tx.send(Response::Export(Err(err))).await;
+                        continue;
+                    }
+                };
+                let tx = tx.clone();
+                let image_cache = image_cache.clone();
+                spawn(async move {
+                    let progress_tx = tx.clone();
+                    let res = export(
+                        fetcher.as_ref(),
+                        &range,
+                        format,
+                        &path,
+                        &image_cache,
+                        // Progress updates are dropped rather than stalling the export
+                        move |done, total| {
+                            let _ = progress_tx.try_send(Response::ExportProgress { done, total });
+                        },
+                    )
+                    .await;
+                    let _ = tx.send(Response::Export(res)).await;
+                });
+            }
+            Request::Offline { site, enabled } => {
+                let changed = if enabled {
+                    offline.insert(site)
+                } else {
+                    offline.remove(&site)
+                };
+                // Strips of the other mode point to other images
+                if changed {
+                    fetchers.remove(&site);
+                    prefetcher.forget(site);
+                }
+            }
+            Request::Subscribe { url } => {
+                let tx = tx.clone();
+                spawn(async move {
+                    let res = subscribe_feed(&url).await.map_err(FetcherErrors::from);
+                    let _ = tx.send(Response::Subscribed(res)).await;
+                });
+            }
         }
     }
 }
 
-async fn download_background(path: PathBuf, url: String) -> Result<()> {
-    let data = reqwest::get(url).await?.bytes().await?;
+/// Fetcher of `site`, built on first use and rebuilt once older than the TTL of `index_cache`.
+///
+/// Sites in `offline` are read from their mirror, which is also the fallback
+/// when the site cannot be reached. The strips prefetched for `site` are dropped
+/// whenever its fetcher is built, the archive may have changed since. When nothing can be
+/// browsed, the error is why the site could not be loaded.
+async fn get_fetcher(
+    fetchers: &mut HashMap<Sites, (Fetcher, Instant)>,
+    site: Sites,
+    offline: &HashSet<Sites>,
+    index_cache: &IndexCache,
+    prefetcher: &Prefetcher,
+) -> Result<Fetcher, FetcherErrors> {
+    if let Some((fetcher, at)) = fetchers.get(&site)
+        && at.elapsed() < index_cache.ttl()
+    {
+        return Ok(fetcher.clone());
+    }
+
+    let online = if offline.contains(&site) {
+        Err(FetcherErrors::Unavailable)
+    } else {
+        build_cached_fetcher(site, index_cache)
+            .await
+            .map(|f| Arc::new(f) as Fetcher)
+    };
+    let fetcher = match online {
+        Ok(fetcher) => fetcher,
+        Err(err) => match build_local_fetcher(site, &site_mirror_dir(site)).await {
+            Some(fetcher) => Arc::new(fetcher) as Fetcher,
+            // The expired one is still better than nothing
+            None => {
+                return fetchers
+                    .get(&site)
+                    .map(|(fetcher, _)| fetcher.clone())
+                    .ok_or(err);
+            }
+        },
+    };
+    prefetcher.forget(site);
+    fetchers.insert(site, (fetcher.clone(), Instant::now()));
+    Ok(fetcher)
+}
+
+async fn download_background(path: PathBuf, url: String, image_cache: &ImageCache) -> Result<()> {
+    let data = image_cache.get(&url).await?;
     let mut file = File::create(path).await?;
     file.write_all(&data).await?;
     Ok(())
 }
 
-async fn get_content_background(ty: RequestStripType, fetcher: Fetcher) -> Option<Strip> {
-    match ty {
-        RequestStripType::Last => fetcher.last().await.ok(),
-        RequestStripType::Random => fetcher.random().await.ok(),
-        RequestStripType::Next(Some(idx)) => fetcher.next(idx).await.ok(),
-        RequestStripType::Prev(Some(idx)) => fetcher.prev(idx).await.ok(),
+async fn get_content_background(
+    site: Sites,
+    ty: RequestStripType,
+    fetcher: Fetcher,
+    prefetcher: &Prefetcher,
+) -> Result<Strip, FetcherErrors> {
+    let neighbour = match ty {
+        RequestStripType::Next(Some(idx)) => Some(idx + 1),
+        RequestStripType::Prev(Some(idx)) => idx.checked_sub(1),
         _ => None,
+    };
+    if let Some(strip) = neighbour.and_then(|idx| prefetcher.resolved(site, idx, &fetcher)) {
+        return Ok(strip);
+    }
+
+    let strip = match ty {
+        RequestStripType::Last => fetcher.last().await,
+        RequestStripType::Random => fetcher.random().await,
+        RequestStripType::Next(Some(idx)) => fetcher.next(idx).await,
+        RequestStripType::Prev(Some(idx)) => fetcher.prev(idx).await,
+        RequestStripType::ByDate(date) => fetcher.by_date(date).await,
+        RequestStripType::Next(None) | RequestStripType::Prev(None) => {
+            return Err(FetcherErrors::OutOfRange);
+        }
+    };
+    strip.map_err(FetcherErrors::from)
+}
+
+/// Strip along with the fetcher that resolved it, strips of an older index are not served
+type Resolved = (Fetcher, Strip);
+
+/// Speculatively resolves the strips around the one being displayed, page and images,
+/// so that Prev and Next are served without waiting for the network.
+#[derive(Clone)]
+struct Prefetcher {
+    resolved: Arc<Mutex<HashMap<(Sites, usize), Resolved>>>,
+    permits: Arc<Semaphore>,
+    image_cache: Arc<ImageCache>,
+}
+
+impl Prefetcher {
+    fn new(image_cache: Arc<ImageCache>) -> Self {
+        Self {
+            resolved: Arc::default(),
+            permits: Arc::new(Semaphore::new(PREFETCH_CONCURRENCY)),
+            image_cache,
+        }
+    }
+
+    fn resolved(&self, site: Sites, idx: usize, fetcher: &Fetcher) -> Option<Strip> {
+        let resolved = self.resolved.lock().unwrap();
+        let (resolved_by, strip) = resolved.get(&(site, idx))?;
+        Arc::ptr_eq(resolved_by, fetcher).then(|| strip.clone())
+    }
+
+    /// Drop the strips resolved for `site`
+    fn forget(&self, site: Sites) {
+        self.resolved
+            .lock()
+            .unwrap()
+            .retain(|(resolved, _), _| *resolved != site);
+    }
+
+    fn prefetch_neighbours(&self, strip: &Strip, fetcher: Fetcher) {
+        let (site, idx) = (strip.site, strip.idx);
+        let neighbours = [
+            strip.has_prev().then(|| idx.checked_sub(1)).flatten(),
+            strip.has_next().then_some(idx + 1),
+        ];
+
+        for neighbour in neighbours.into_iter().flatten() {
+            if self.resolved(site, neighbour, &fetcher).is_some() {
+                continue;
+            }
+            // Out of budget: the user is navigating faster than we can prefetch
+            let Ok(permit) = self.permits.clone().try_acquire_owned() else {
+                return;
+            };
+
+            let prefetcher = self.clone();
+            let fetcher = fetcher.clone();
+            spawn(async move {
+                let _permit = permit;
+                let strip = if neighbour < idx {
+                    fetcher.prev(idx).await
+                } else {
+                    fetcher.next(idx).await
+                };
+                let Ok(strip) = strip else {
+                    return;
+                };
+
+                {
+                    let mut resolved = prefetcher.resolved.lock().unwrap();
+                    if resolved.len() >= PREFETCH_CAPACITY {
+                        resolved.clear();
+                    }
+                    resolved.insert((site, strip.idx), (fetcher, strip.clone()));
+                }
+                for url in strip.image_urls() {
+                    let _ = prefetcher.image_cache.get(url).await;
+                }
+            });
+        }
     }
 }
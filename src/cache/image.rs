@@ -0,0 +1,278 @@
+use std::{
+    collections::{HashMap, VecDeque},
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex},
+    time::SystemTime,
+};
+
+use anyhow::{Result, bail};
+use sha2::{Digest, Sha256};
+use tokio::fs;
+
+use crate::{http::HttpClient, mirror::mirror_dir};
+
+/// Encoded image bytes, cheap to clone
+pub type ImageData = Arc<[u8]>;
+
+/// Default size of the in memory tier
+pub const DEFAULT_MEMORY_LIMIT: usize = 64 * 1024 * 1024;
+
+/// Default size of the on disk tier
+pub const DEFAULT_DISK_LIMIT: u64 = 512 * 1024 * 1024;
+
+/// Two tiers cache of strip images addressed by the sha256 of their url.
+///
+/// Lookups hit the memory LRU first, then the optional disk tier and finally the network.
+pub struct ImageCache {
+    memory: Mutex<MemoryTier>,
+    disk: Option<DiskTier>,
+    http: HttpClient,
+    /// Only `file://` urls below it are read, the others may come from any feed
+    local_dir: PathBuf,
+}
+
+impl Default for ImageCache {
+    fn default() -> Self {
+        Self::new(
+            DEFAULT_MEMORY_LIMIT,
+            Some((super::cache_dir().join("images"), DEFAULT_DISK_LIMIT)),
+        )
+    }
+}
+
+impl ImageCache {
+    /// `disk` holds the directory and the size limit in bytes of the disk tier
+    pub fn new(memory_limit: usize, disk: Option<(PathBuf, u64)>) -> Self {
+        Self {
+            memory: Mutex::new(MemoryTier::new(memory_limit)),
+            disk: disk.map(|(dir, limit)| DiskTier { dir, limit }),
+            http: HttpClient::shared(),
+            local_dir: mirror_dir(),
+        }
+    }
+
+    /// Read `file://` urls below `dir` rather than below [`mirror_dir`]
+    pub fn with_local_dir(mut self, dir: PathBuf) -> Self {
+        self.local_dir = dir;
+        self
+    }
+
+    pub async fn get(&self, url: &str) -> Result<ImageData> {
+        // Mirrored strips are on disk already
+        if let Some(path) = url.strip_prefix("file://") {
+            return self.read_local(Path::new(path)).await;
+        }
+
+        let key = Self::key(url);
+        if let Some(data) = self.memory.lock().unwrap().get(&key) {
+            return Ok(data);
+        }
+
+        let data = match self.disk.as_ref() {
+            Some(disk) => match disk.get(&key).await {
+                Some(data) => data,
+                None => {
+                    let data = self.download(url).await?;
+                    let _ = disk.insert(&key, &data).await;
+                    data
+                }
+            },
+            None => self.download(url).await?,
+        };
+
+        self.memory.lock().unwrap().insert(key, data.clone());
+        Ok(data)
+    }
+
+    /// Whether `url` is available without downloading it
+    pub async fn contains(&self, url: &str) -> bool {
+        let key = Self::key(url);
+        if self.memory.lock().unwrap().entries.contains_key(&key) {
+            return true;
+        }
+        match self.disk.as_ref() {
+            Some(disk) => fs::try_exists(disk.dir.join(&key)).await.unwrap_or(false),
+            None => false,
+        }
+    }
+
+    async fn read_local(&self, path: &Path) -> Result<ImageData> {
+        // Resolved first, so that `..` and links cannot leave the directory
+        let path = fs::canonicalize(path).await?;
+        let local_dir = fs::canonicalize(&self.local_dir).await?;
+        if !path.starts_with(&local_dir) {
+            bail!("{} is not in {}", path.display(), local_dir.display());
+        }
+        Ok(fs::read(path).await?.into())
+    }
+
+    async fn download(&self, url: &str) -> Result<ImageData> {
+        Ok(self.http.bytes(url).await?.into())
+    }
+
+    fn key(url: &str) -> String {
+        Sha256::digest(url.as_bytes())
+            .iter()
+            .map(|byte| format!("{byte:02x}"))
+            .collect()
+    }
+}
+
+struct MemoryTier {
+    entries: HashMap<String, ImageData>,
+    order: VecDeque<String>,
+    size: usize,
+    limit: usize,
+}
+
+impl MemoryTier {
+    fn new(limit: usize) -> Self {
+        Self {
+            entries: HashMap::default(),
+            order: VecDeque::default(),
+            size: 0,
+            limit,
+        }
+    }
+
+    fn get(&mut self, key: &str) -> Option<ImageData> {
+        let data = self.entries.get(key)?.clone();
+        self.touch(key);
+        Some(data)
+    }
+
+    fn insert(&mut self, key: String, data: ImageData) {
+        if data.len() > self.limit {
+            return;
+        }
+        if let Some(old) = self.entries.insert(key.clone(), data.clone()) {
+            self.size -= old.len();
+            self.touch(&key);
+        } else {
+            self.order.push_back(key);
+        }
+        self.size += data.len();
+
+        while self.size > self.limit {
+            let Some(evicted) = self.order.pop_front() else {
+                break;
+            };
+            if let Some(old) = self.entries.remove(&evicted) {
+                self.size -= old.len();
+            }
+        }
+    }
+
+    fn touch(&mut self, key: &str) {
+        if let Some(pos) = self.order.iter().position(|k| k == key) {
+            let key = self.order.remove(pos).unwrap();
+            self.order.push_back(key);
+        }
+    }
+}
+
+struct DiskTier {
+    dir: PathBuf,
+    limit: u64,
+}
+
+impl DiskTier {
+    async fn get(&self, key: &str) -> Option<ImageData> {
+        let path = self.dir.join(key);
+        let data = fs::read(&path).await.ok()?;
+        // Refresh the modification time, used as last access time on eviction
+        if let Ok(file) = fs::File::options().append(true).open(&path).await {
+            let _ = file.into_std().await.set_modified(SystemTime::now());
+        }
+        Some(data.into())
+    }
+
+    async fn insert(&self, key: &str, data: &[u8]) -> Result<()> {
+        fs::create_dir_all(&self.dir).await?;
+        fs::write(self.dir.join(key), data).await?;
+        self.evict().await
+    }
+
+    /// Remove the least recently used files until the tier fits its limit
+    async fn evict(&self) -> Result<()> {
+        let mut files = Vec::new();
+        let mut size = 0;
+        let mut entries = fs::read_dir(&self.dir).await?;
+        while let Some(entry) = entries.next_entry().await? {
+            let metadata = entry.metadata().await?;
+            size += metadata.len();
+            files.push((metadata.modified()?, metadata.len(), entry.path()));
+        }
+
+        files.sort_by_key(|(modified, _, _)| *modified);
+        for (_, len, path) in files {
+            if size <= self.limit {
+                break;
+            }
+            fs::remove_file(path).await?;
+            size -= len;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::ImageCache;
+    use crate::fetcher::fixture::FixtureServer;
+
+    #[tokio::test]
+    async fn test_image_cache_tiers() {
+        let dir = std::env::temp_dir().join(format!("daily-strip-images-{}", std::process::id()));
+        let routes = [("/a.png", "comic.html"), ("/b.png", "index.html")];
+        let server = FixtureServer::start("xkcd", &routes).await;
+        let (a, b) = (
+            format!("{}/a.png", server.url()),
+            format!("{}/b.png", server.url()),
+        );
+
+        let cache = ImageCache::new(200, Some((dir.clone(), 1024 * 1024)));
+        let first = cache.get(&a).await.unwrap();
+        assert!(cache.get(&b).await.is_ok());
+        drop(server);
+
+        // Served from memory or disk once the site is gone
+        assert_eq!(cache.get(&a).await.unwrap(), first);
+        let cache = ImageCache::new(200, Some((dir.clone(), 1024 * 1024)));
+        assert!(cache.contains(&b).await);
+        assert_eq!(cache.get(&a).await.unwrap(), first);
+
+        // Without a disk tier nothing survives
+        let cache = ImageCache::new(200, None);
+        assert!(!cache.contains(&a).await);
+        assert!(cache.get(&a).await.is_err());
+
+        let _ = std::fs::remove_dir_all(dir);
+    }
+
+    #[tokio::test]
+    async fn test_image_cache_local_dir() {
+        let dir = std::env::temp_dir().join(format!("daily-strip-local-{}", std::process::id()));
+        let mirror = dir.join("mirror");
+        std::fs::create_dir_all(&mirror).unwrap();
+        std::fs::write(mirror.join("strip.png"), "strip").unwrap();
+        std::fs::write(dir.join("secret.txt"), "secret").unwrap();
+
+        let cache = ImageCache::new(200, None).with_local_dir(mirror.clone());
+        let url = |path: &str| format!("file://{}", dir.join(path).display());
+        assert_eq!(
+            cache.get(&url("mirror/strip.png")).await.unwrap().as_ref(),
+            b"strip"
+        );
+        assert!(cache.get(&url("secret.txt")).await.is_err());
+        assert!(cache.get(&url("mirror/../secret.txt")).await.is_err());
+        assert!(
+            ImageCache::new(200, None)
+                .get(&url("mirror/strip.png"))
+                .await
+                .is_err()
+        );
+
+        let _ = std::fs::remove_dir_all(dir);
+    }
+}
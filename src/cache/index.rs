@@ -0,0 +1,98 @@
+use std::{
+    collections::HashMap,
+    path::PathBuf,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use tokio::fs;
+
+use crate::{Sites, Strip, http::Validators};
+
+/// Archive indexes older than this are reloaded from the site
+pub const DEFAULT_INDEX_TTL: Duration = Duration::from_secs(12 * 60 * 60);
+
+/// Images listed along with the posts of an archive, by url of the post, before the strips are
+/// resolved
+pub type PostImages = HashMap<String, Vec<String>>;
+
+/// Parsed archive index of a site persisted to disk
+#[derive(Debug, Serialize, Deserialize)]
+pub struct CachedIndex {
+    /// Seconds since the unix epoch at which the index was stored
+    pub timestamp: u64,
+    pub posts: Vec<Strip>,
+    /// Validators of the archive pages the posts were parsed from
+    #[serde(default)]
+    pub validators: Validators,
+    #[serde(default)]
+    pub images: PostImages,
+}
+
+impl CachedIndex {
+    pub fn age(&self) -> Duration {
+        let stored = UNIX_EPOCH + Duration::from_secs(self.timestamp);
+        SystemTime::now().duration_since(stored).unwrap_or_default()
+    }
+}
+
+/// Stores one json file per site containing its archive index
+#[derive(Debug, Clone)]
+pub struct IndexCache {
+    dir: PathBuf,
+    ttl: Duration,
+}
+
+impl Default for IndexCache {
+    fn default() -> Self {
+        Self::new(super::cache_dir().join("index"), DEFAULT_INDEX_TTL)
+    }
+}
+
+impl IndexCache {
+    pub fn new(dir: PathBuf, ttl: Duration) -> Self {
+        Self { dir, ttl }
+    }
+
+    pub fn ttl(&self) -> Duration {
+        self.ttl
+    }
+
+    /// Whether `index` can be used without contacting the site
+    pub fn is_fresh(&self, index: &CachedIndex) -> bool {
+        index.age() < self.ttl
+    }
+
+    pub async fn load(&self, site: Sites) -> Option<CachedIndex> {
+        let data = fs::read(self.path(site)).await.ok()?;
+        serde_json::from_slice(&data).ok()
+    }
+
+    pub async fn store(
+        &self,
+        site: Sites,
+        posts: &[Strip],
+        validators: &Validators,
+        images: &PostImages,
+    ) -> Result<()> {
+        let timestamp = SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs();
+        let data = serde_json::to_vec(&CachedIndex {
+            timestamp,
+            posts: posts.to_vec(),
+            validators: validators.clone(),
+            images: images.clone(),
+        })?;
+        fs::create_dir_all(&self.dir).await?;
+        fs::write(self.path(site), data).await?;
+        Ok(())
+    }
+
+    fn path(&self, site: Sites) -> PathBuf {
+        match site {
+            // Custom sites are numbered in registration order, their id is what stays the same
+            Sites::Custom(_) => self.dir.join(format!("custom-{}.json", site.id())),
+            _ => self.dir.join(format!("{site:?}.json")),
+        }
+    }
+}
@@ -0,0 +1,14 @@
+use std::path::PathBuf;
+
+mod image;
+mod index;
+
+pub use image::{ImageCache, ImageData};
+pub use index::{IndexCache, PostImages};
+
+/// Root directory used by every on-disk cache of the app
+pub fn cache_dir() -> PathBuf {
+    dirs::cache_dir()
+        .unwrap_or_else(std::env::temp_dir)
+        .join("daily-strip")
+}
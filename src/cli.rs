@@ -0,0 +1,320 @@
+use std::{path::PathBuf, str::FromStr, sync::Arc, time::Duration};
+
+use anyhow::{Result, anyhow};
+use chrono::NaiveDate;
+use clap::{Parser, Subcommand};
+use rand::{rng, seq::IndexedRandom};
+use tokio::runtime::Builder;
+
+use crate::{
+    Fetcher, Sites, Strip, Url,
+    cache::{ImageCache, IndexCache},
+    export::{ExportFormat, ExportRange, export},
+    feed::{DEFAULT_FEED_LENGTH, FeedFormat, merged_feed, recent_strips, site_feed},
+    fetcher::{build_cached_fetcher, subscribe_feed},
+    mirror::{DEFAULT_MIRROR_CONCURRENCY, DEFAULT_MIRROR_DELAY, MirrorOptions, mirror, mirror_dir},
+    settings::config_errors,
+};
+
+#[derive(Debug, Parser)]
+#[command(version, about = "Comic strips viewer", long_about = None)]
+pub struct Cli {
+    /// Launch the graphical frontend when no command is given
+    #[command(subcommand)]
+    pub command: Option<Command>,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum Command {
+    /// Print the latest strip of a site
+    Last {
+        #[arg(value_parser = parse_site)]
+        site: Sites,
+        /// Save the strip image to this file
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+    },
+    /// Print a random strip, from a random site unless one is given
+    Random {
+        #[arg(short, long, value_parser = parse_site)]
+        site: Option<Sites>,
+        /// Also pick among the NSFW sites when no site is given
+        #[arg(long, conflicts_with = "site")]
+        nsfw: bool,
+        /// Save the strip image to this file
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+    },
+    /// Print the strip at the given position, 0 being the oldest one
+    Get {
+        #[arg(value_parser = parse_site)]
+        site: Sites,
+        #[arg(short, long, required_unless_present = "date")]
+        idx: Option<usize>,
+        /// Strip published closest to this date (`YYYY-MM-DD`), when the site tells dates
+        #[arg(short, long, conflicts_with = "idx")]
+        date: Option<NaiveDate>,
+        /// Save the strip image to this file
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+    },
+    /// List the supported sites as `id<TAB>name<TAB>homepage`
+    ListSites,
+    /// Follow the RSS or Atom feed of a webcomic as a new site
+    Subscribe {
+        /// Url of the feed
+        url: String,
+    },
+    /// Export a range of strips, the whole archive by default, to a comic book or an ebook
+    Export {
+        #[arg(value_parser = parse_site)]
+        site: Sites,
+        /// First strip to export, 0 being the oldest one
+        #[arg(long)]
+        from: Option<usize>,
+        /// Last strip to export, included
+        #[arg(long)]
+        to: Option<usize>,
+        /// `cbz`, `epub` or `pdf`
+        #[arg(short, long, default_value_t)]
+        format: ExportFormat,
+        /// Defaults to `<site>.<format>`
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+    },
+    /// Save the whole archive with a manifest, resuming previous runs
+    Mirror {
+        #[arg(value_parser = parse_site)]
+        site: Sites,
+        /// Directory holding the mirrors, the site is saved in a sub directory named after its id
+        #[arg(short, long)]
+        dir: Option<PathBuf>,
+        /// Strips downloaded at the same time
+        #[arg(short, long, default_value_t = DEFAULT_MIRROR_CONCURRENCY)]
+        concurrency: usize,
+        /// Pause after each strip, in milliseconds
+        #[arg(long, default_value_t = DEFAULT_MIRROR_DELAY.as_millis() as u64)]
+        delay: u64,
+    },
+    /// Print a feed of the latest strips, merged when several sites are given
+    Feed {
+        #[arg(required = true, value_parser = parse_site)]
+        sites: Vec<Sites>,
+        #[arg(short, long, default_value_t)]
+        format: FeedFormat,
+        /// Strips per site
+        #[arg(short = 'n', long, default_value_t = DEFAULT_FEED_LENGTH)]
+        count: usize,
+        /// Write the feed to this file
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+    },
+    /// Serve the strips as a JSON API and a web viewer
+    #[cfg(feature = "server")]
+    Serve {
+        /// Address to listen on
+        #[arg(short, long, default_value = "127.0.0.1:8080")]
+        addr: std::net::SocketAddr,
+    },
+    /// Browse the strips in the terminal
+    #[cfg(feature = "tui_frontend")]
+    Tui,
+}
+
+/// Accept both the site id (e.g. `xkcd`) and its display name
+fn parse_site(value: &str) -> Result<Sites, String> {
+    Sites::from_id(value)
+        .or_else(|| Sites::from_str(value).ok())
+        .ok_or_else(|| format!("unknown site `{value}`, see `list-sites`"))
+}
+
+/// Run a command printing the resulting strip as json on stdout
+pub fn run(command: Command) -> Result<()> {
+    #[cfg(feature = "tui_frontend")]
+    if let Command::Tui = command {
+        use crate::{
+            backend::start_backend,
+            frontend::{Runnable, tui::TuiFrontend},
+        };
+
+        let (handle, tx, rx) = start_backend();
+        return TuiFrontend::run(handle, tx, rx);
+    }
+
+    for err in config_errors() {
+        eprintln!("Ignoring {err}");
+    }
+    let rt = Builder::new_multi_thread().enable_all().build()?;
+    rt.block_on(run_async(command))
+}
+
+async fn run_async(command: Command) -> Result<()> {
+    let (strip, output) = match command {
+        Command::ListSites => {
+            for site in Sites::sites_sorted() {
+                println!("{}\t{site}\t{}", site.id(), site.homepage());
+            }
+            return Ok(());
+        }
+        Command::Subscribe { url } => {
+            let site = subscribe_feed(&url).await?;
+            println!("{}\t{site}\t{}", site.id(), site.homepage());
+            return Ok(());
+        }
+        Command::Export {
+            site,
+            from,
+            to,
+            format,
+            output,
+        } => {
+            let range = match (from, to) {
+                (None, None) => ExportRange::All,
+                (from, to) => ExportRange::Range(from.unwrap_or(0)..=to.unwrap_or(usize::MAX)),
+            };
+            let path = output
+                .unwrap_or_else(|| PathBuf::from(format!("{}.{}", site.id(), format.extension())));
+            let images = ImageCache::default();
+            let fetcher = fetcher(site).await?;
+            let report = export(&fetcher, &range, format, &path, &images, |done, total| {
+                eprint!("\rExporting {done}/{total}")
+            })
+            .await?;
+            eprintln!();
+            println!(
+                "Exported {} strips to {}, {} failed",
+                report.exported,
+                path.display(),
+                report.failed
+            );
+            return Ok(());
+        }
+        Command::Mirror {
+            site,
+            dir,
+            concurrency,
+            delay,
+        } => {
+            let dir = dir.unwrap_or_else(mirror_dir).join(site.id());
+            let options = MirrorOptions {
+                concurrency,
+                delay: Duration::from_millis(delay),
+            };
+            let fetcher = Arc::new(fetcher(site).await?);
+            let report = mirror(fetcher, &dir, options, |done, total| {
+                eprint!("\rMirroring {done}/{total}")
+            })
+            .await?;
+            eprintln!();
+            println!(
+                "{} strips downloaded, {} already mirrored, {} failed in {}",
+                report.downloaded,
+                report.skipped,
+                report.failed,
+                dir.display()
+            );
+            return Ok(());
+        }
+        Command::Feed {
+            sites,
+            format,
+            count,
+            output,
+        } => return feed(&sites, format, count, output).await,
+        #[cfg(feature = "server")]
+        Command::Serve { addr } => return crate::server::serve(addr).await,
+        Command::Last { site, output } => (fetcher(site).await?.last().await?, output),
+        Command::Random { site, nsfw, output } => {
+            let site = match site {
+                Some(site) => site,
+                None => *Sites::sites_sorted()
+                    .into_iter()
+                    .filter(|site| nsfw || !site.is_nsfw())
+                    .collect::<Vec<_>>()
+                    .choose(&mut rng())
+                    .ok_or_else(|| anyhow!("no site available"))?,
+            };
+            (fetcher(site).await?.random().await?, output)
+        }
+        Command::Get {
+            site,
+            idx,
+            date,
+            output,
+        } => {
+            let fetcher = fetcher(site).await?;
+            let strip = match (idx, date) {
+                (Some(idx), _) => fetcher.get(idx).await?,
+                (None, Some(date)) => fetcher.by_date(date).await?,
+                (None, None) => unreachable!("required by clap"),
+            };
+            (strip, output)
+        }
+        #[cfg(feature = "tui_frontend")]
+        Command::Tui => unreachable!("handled by run"),
+    };
+
+    if let Some(path) = output {
+        save(&strip, path).await?;
+    }
+    println!("{}", serde_json::to_string_pretty(&strip)?);
+    Ok(())
+}
+
+async fn feed(
+    sites: &[Sites],
+    format: FeedFormat,
+    count: usize,
+    output: Option<PathBuf>,
+) -> Result<()> {
+    let feed = if let [site] = sites {
+        site_feed(
+            *site,
+            &recent_strips(&fetcher(*site).await?, count).await?,
+            format,
+        )
+    } else {
+        let mut strips = Vec::with_capacity(sites.len());
+        for site in sites {
+            // A broken site shouldn't take the whole feed down
+            match async { recent_strips(&fetcher(*site).await?, count).await }.await {
+                Ok(recent) => strips.push(recent),
+                Err(err) => eprintln!("Skipping {site}: {err}"),
+            }
+        }
+        merged_feed(&strips, format)
+    };
+
+    match output {
+        Some(path) => tokio::fs::write(path, feed).await?,
+        None => println!("{feed}"),
+    }
+    Ok(())
+}
+
+async fn fetcher(site: Sites) -> Result<impl Fetcher> {
+    build_cached_fetcher(site, &IndexCache::default())
+        .await
+        .map_err(|err| anyhow!("failed to load the archive of {site}: {err}"))
+}
+
+/// Save the images of `strip`, the ones following the first as `name_2.ext` and so on
+async fn save(strip: &Strip, path: PathBuf) -> Result<()> {
+    let images = ImageCache::default();
+    for (pos, url) in strip.image_urls().into_iter().enumerate() {
+        let data = images.get(url).await?;
+        let path = match pos {
+            0 => path.clone(),
+            _ => {
+                let stem = path.file_stem().unwrap_or_default().to_string_lossy();
+                let name = match path.extension() {
+                    Some(ext) => format!("{stem}_{}.{}", pos + 1, ext.to_string_lossy()),
+                    None => format!("{stem}_{}", pos + 1),
+                };
+                path.with_file_name(name)
+            }
+        };
+        tokio::fs::write(path, data).await?;
+    }
+    Ok(())
+}
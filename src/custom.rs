@@ -0,0 +1,619 @@
+use std::{
+    fmt,
+    path::{Path, PathBuf},
+    sync::{LazyLock, OnceLock, RwLock},
+};
+
+use anyhow::{Result, bail};
+use scraper::Selector;
+use serde::{Deserialize, Deserializer, Serialize, Serializer, de};
+use strum::IntoEnumIterator;
+
+use crate::{Fetcher, Sites, settings::config_dir};
+
+/// What the site pickers and the server show of a site added at runtime
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SiteInfo {
+    /// Short identifier, as used by the command line and the server
+    pub id: String,
+    pub name: String,
+    /// Url of the homepage, scheme included
+    pub homepage: String,
+    #[serde(default)]
+    pub nsfw: bool,
+}
+
+impl SiteInfo {
+    fn validate(&self) -> Result<()> {
+        if self.id.is_empty() || self.name.is_empty() {
+            bail!("`id` and `name` must not be empty");
+        }
+        Ok(())
+    }
+}
+
+/// Site described by a definition file instead of a dedicated fetcher.
+///
+/// The strips are the elements matching `list_selector` in the page at `index_url`, each one
+/// linking to the page of a strip where the image matches `image_selector`:
+///
+/// ```toml
+/// id = "my-comic"
+/// name = "My Comic"
+/// homepage = "https://mycomic.example"
+/// index_url = "https://mycomic.example/archive"
+/// list_selector = "ul.archive a"
+/// image_selector = "#comic img"
+/// order = "oldest_first"
+/// ```
+///
+/// WordPress sites are listed through their REST API instead, `index_url` being the endpoint of the
+/// posts, like `https://mycomic.example/wp-json/wp/v2/posts` or `/wp-json/wp/v2/comic` with Comic
+/// Easel, and `image_selector` matching the comic in the content of a post:
+///
+/// ```toml
+/// kind = "wordpress"
+/// index_url = "https://mycomic.example/wp-json/wp/v2/comic"
+/// image_selector = "#comic img"
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SiteDefinition {
+    #[serde(flatten)]
+    pub info: SiteInfo,
+    #[serde(default)]
+    pub kind: SiteKind,
+    /// Archive page listing the strips, or the REST API endpoint of WordPress sites
+    pub index_url: String,
+    /// Elements of the archive page linking to each strip, unused by WordPress sites
+    #[serde(default)]
+    pub list_selector: String,
+    /// Attribute of those elements holding the link
+    #[serde(default = "default_link_attr")]
+    pub link_attr: String,
+    /// Attribute of those elements holding the title, their text when unset
+    #[serde(default)]
+    pub title_attr: Option<String>,
+    /// Images of the strip page, strips made of several images match more than once
+    pub image_selector: String,
+    #[serde(default = "default_image_attr")]
+    pub image_attr: String,
+    /// Prepended to relative links and images, which are otherwise resolved against their page
+    #[serde(default)]
+    pub url_prefix: Option<String>,
+    #[serde(default)]
+    pub order: Order,
+}
+
+fn default_link_attr() -> String {
+    "href".to_owned()
+}
+
+fn default_image_attr() -> String {
+    "src".to_owned()
+}
+
+/// Where the strips of a site are listed
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SiteKind {
+    /// Scraped from the archive page
+    #[default]
+    Archive,
+    /// Posts of the WordPress REST API
+    Wordpress,
+}
+
+/// How the archive page lists the strips
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Order {
+    #[default]
+    NewestFirst,
+    OldestFirst,
+}
+
+impl SiteDefinition {
+    /// Definition read from a `.toml` or `.json` file
+    pub fn load(path: &Path) -> Result<Self> {
+        let data = std::fs::read_to_string(path)?;
+        let definition: Self = match path.extension().and_then(|ext| ext.to_str()) {
+            Some("toml") => toml::from_str(&data)?,
+            Some("json") => serde_json::from_str(&data)?,
+            _ => bail!("unsupported format, use .toml or .json"),
+        };
+        definition.validate()?;
+        Ok(definition)
+    }
+
+    fn validate(&self) -> Result<()> {
+        self.info.validate()?;
+        let selectors = match self.kind {
+            SiteKind::Archive => vec![&self.list_selector, &self.image_selector],
+            SiteKind::Wordpress => vec![&self.image_selector],
+        };
+        for selector in selectors {
+            if let Err(err) = Selector::parse(selector) {
+                bail!("invalid selector `{selector}`: {err}");
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Webcomic followed through its feed, subscribed to from the frontends
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FeedSubscription {
+    #[serde(flatten)]
+    pub info: SiteInfo,
+    /// Url of the RSS or Atom feed
+    pub url: String,
+}
+
+/// Content of the subscriptions file
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct Subscriptions {
+    #[serde(default)]
+    feed: Vec<FeedSubscription>,
+}
+
+/// Builds the fetcher of a site registered by another crate, given the site its strips belong to
+pub type FetcherFactory = dyn Fn(Sites) -> Box<dyn Fetcher + Send + Sync> + Send + Sync;
+
+/// Where the strips of a site added at runtime come from
+enum Source {
+    Definition(SiteDefinition),
+    Feed(FeedSubscription),
+    Fetcher {
+        info: SiteInfo,
+        build: Box<FetcherFactory>,
+    },
+}
+
+impl Source {
+    fn info(&self) -> &SiteInfo {
+        match self {
+            Source::Definition(definition) => &definition.info,
+            Source::Feed(feed) => &feed.info,
+            Source::Fetcher { info, .. } => info,
+        }
+    }
+}
+
+/// Site added at runtime, cheap to copy around like the built-in ones
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct CustomSite(usize);
+
+impl CustomSite {
+    fn source(self) -> &'static Source {
+        REGISTRY.read().unwrap()[self.0]
+    }
+
+    pub fn info(self) -> &'static SiteInfo {
+        self.source().info()
+    }
+
+    /// `None` unless the site was registered from a definition
+    pub fn definition(self) -> Option<&'static SiteDefinition> {
+        match self.source() {
+            Source::Definition(definition) => Some(definition),
+            _ => None,
+        }
+    }
+
+    /// Whether the site is listed through the WordPress REST API
+    pub fn is_wordpress(self) -> bool {
+        self.definition()
+            .is_some_and(|definition| definition.kind == SiteKind::Wordpress)
+    }
+
+    /// `None` unless the site is a feed subscription
+    pub fn feed(self) -> Option<&'static FeedSubscription> {
+        match self.source() {
+            Source::Feed(feed) => Some(feed),
+            _ => None,
+        }
+    }
+
+    /// Archive page or feed, the homepage of sites registered with their own fetcher
+    pub fn fetch_url(self) -> &'static str {
+        match self.source() {
+            Source::Definition(definition) => &definition.index_url,
+            Source::Feed(feed) => &feed.url,
+            Source::Fetcher { info, .. } => &info.homepage,
+        }
+    }
+
+    /// Fetcher of a site registered with its own implementation, not loaded yet
+    pub(crate) fn build_fetcher(self) -> Option<Box<dyn Fetcher + Send + Sync>> {
+        match self.source() {
+            Source::Fetcher { build, .. } => Some(build(Sites::Custom(self))),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for CustomSite {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let info = self.info();
+        match info.nsfw {
+            true => write!(f, "{} [NSFW]", info.name),
+            false => f.write_str(&info.name),
+        }
+    }
+}
+
+/// Stored by id, indexes depend on the order the sites were registered in
+impl Serialize for CustomSite {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&self.info().id)
+    }
+}
+
+impl<'de> Deserialize<'de> for CustomSite {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let id = String::deserialize(deserializer)?;
+        match Sites::from_id(&id) {
+            Some(Sites::Custom(site)) => Ok(site),
+            _ => Err(de::Error::custom(format!("unknown site `{id}`"))),
+        }
+    }
+}
+
+/// Sources are never dropped, so that sites can hand out `'static` ids and urls
+static REGISTRY: LazyLock<RwLock<Vec<&'static Source>>> = LazyLock::new(|| {
+    let (mut sources, mut skipped) = load_dir(&sites_dir());
+    let path = subscriptions_path();
+    let feeds = load_subscriptions(&path).unwrap_or_else(|err| {
+        skipped.push(format!("{}: {err}", path.display()));
+        Vec::new()
+    });
+    for feed in feeds {
+        match check_unique(&sources, &feed.info) {
+            Ok(_) => sources.push(Box::leak(Box::new(Source::Feed(feed)))),
+            Err(err) => skipped.push(format!("the feed {}: {err}", feed.url)),
+        }
+    }
+    let _ = SKIPPED.set(skipped);
+    RwLock::new(sources)
+});
+
+/// Definitions and subscriptions left out of `REGISTRY` when it was loaded
+static SKIPPED: OnceLock<Vec<String>> = OnceLock::new();
+
+/// Definition files and feed subscriptions ignored when loading the custom sites, and why
+pub fn skipped() -> &'static [String] {
+    LazyLock::force(&REGISTRY);
+    SKIPPED.get().map(Vec::as_slice).unwrap_or_default()
+}
+
+/// Directory holding the definition files, one site per file
+pub fn sites_dir() -> PathBuf {
+    config_dir().join("sites")
+}
+
+/// Valid definitions of `dir` sorted by file name, along with why the broken ones were skipped
+fn load_dir(dir: &Path) -> (Vec<&'static Source>, Vec<String>) {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return (Vec::new(), Vec::new());
+    };
+    let mut paths: Vec<_> = entries
+        .filter_map(|entry| Some(entry.ok()?.path()))
+        .collect();
+    paths.sort();
+
+    let mut sources: Vec<&'static Source> = Vec::new();
+    let mut skipped = Vec::new();
+    for path in paths {
+        let res = SiteDefinition::load(&path)
+            .and_then(|definition| check_unique(&sources, &definition.info).map(|_| definition));
+        match res {
+            Ok(definition) => sources.push(Box::leak(Box::new(Source::Definition(definition)))),
+            Err(err) => skipped.push(format!("{}: {err}", path.display())),
+        }
+    }
+    (sources, skipped)
+}
+
+/// Fail when the id or the name of `info` is already taken
+fn check_unique(registered: &[&Source], info: &SiteInfo) -> Result<()> {
+    let builtin = Sites::iter().any(|site| site.id() == info.id || site.to_string() == info.name);
+    let custom = registered
+        .iter()
+        .map(|source| source.info())
+        .any(|other| other.id == info.id || other.name == info.name);
+    match builtin || custom {
+        true => bail!(
+            "a site named `{}` or `{}` exists already",
+            info.id,
+            info.name
+        ),
+        false => Ok(()),
+    }
+}
+
+fn push(source: Source) -> Result<Sites> {
+    let mut registry = REGISTRY.write().unwrap();
+    check_unique(&registry, source.info())?;
+    registry.push(Box::leak(Box::new(source)));
+    Ok(Sites::Custom(CustomSite(registry.len() - 1)))
+}
+
+/// Add a site to those read from [`sites_dir`]
+pub fn register(definition: SiteDefinition) -> Result<Sites> {
+    definition.validate()?;
+    push(Source::Definition(definition))
+}
+
+/// Add a site browsed by a fetcher of another crate.
+///
+/// `build` is called each time the site is opened, the fetcher is reloaded right after. The site
+/// is listed by the frontends next to the built-in ones, so register it before starting them.
+/// The fetcher builds its strips with [`crate::Strip::new`] and [`crate::StripType::at`].
+pub fn register_fetcher(
+    info: SiteInfo,
+    build: impl Fn(Sites) -> Box<dyn Fetcher + Send + Sync> + Send + Sync + 'static,
+) -> Result<Sites> {
+    info.validate()?;
+    push(Source::Fetcher {
+        info,
+        build: Box::new(build),
+    })
+}
+
+/// Add a site following `feed` for this session only, see [`subscribe`] to keep it
+pub fn register_feed(feed: FeedSubscription) -> Result<Sites> {
+    feed.info.validate()?;
+    push(Source::Feed(feed))
+}
+
+/// Add a site following `feed` and save it to the subscriptions file
+pub fn subscribe(feed: FeedSubscription) -> Result<Sites> {
+    let site = register_feed(feed.clone())?;
+    save_subscription(&subscriptions_path(), feed)?;
+    Ok(site)
+}
+
+/// Site already following the feed at `url`
+pub fn feed_site(url: &str) -> Option<Sites> {
+    sites().into_iter().find(|site| match site {
+        Sites::Custom(site) => site.feed().is_some_and(|feed| feed.url == url),
+        _ => false,
+    })
+}
+
+/// Info of a new site called `name`, with an id and a name no other site has
+pub fn free_info(name: &str, homepage: String) -> SiteInfo {
+    let mut slug = String::new();
+    for c in name.to_lowercase().chars() {
+        match c.is_ascii_alphanumeric() {
+            true => slug.push(c),
+            false if !slug.is_empty() && !slug.ends_with('-') => slug.push('-'),
+            false => {}
+        }
+    }
+    let slug = match slug.trim_end_matches('-') {
+        "" => "feed",
+        slug => slug,
+    };
+    let name = match name.trim() {
+        "" => slug,
+        name => name,
+    };
+
+    let sites = Sites::all();
+    let taken = |id: &str, name: &str| {
+        sites
+            .iter()
+            .any(|site| site.id() == id || site.to_string() == name)
+    };
+    let (mut id, mut unique_name) = (slug.to_owned(), name.to_owned());
+    for n in 2.. {
+        if !taken(&id, &unique_name) {
+            break;
+        }
+        id = format!("{slug}-{n}");
+        unique_name = format!("{name} ({n})");
+    }
+    SiteInfo {
+        id,
+        name: unique_name,
+        homepage,
+        nsfw: false,
+    }
+}
+
+/// File listing the feeds subscribed to
+pub fn subscriptions_path() -> PathBuf {
+    config_dir().join("feeds.toml")
+}
+
+fn load_subscriptions(path: &Path) -> Result<Vec<FeedSubscription>> {
+    match std::fs::read_to_string(path) {
+        Ok(data) => Ok(toml::from_str::<Subscriptions>(&data)?.feed),
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => Ok(Vec::new()),
+        Err(err) => Err(err.into()),
+    }
+}
+
+fn save_subscription(path: &Path, feed: FeedSubscription) -> Result<()> {
+    let mut feeds = load_subscriptions(path)?;
+    feeds.push(feed);
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir)?;
+    }
+    std::fs::write(path, toml::to_string(&Subscriptions { feed: feeds })?)?;
+    Ok(())
+}
+
+/// Every registered site, in registration order
+pub fn sites() -> Vec<Sites> {
+    let len = REGISTRY.read().unwrap().len();
+    (0..len).map(|idx| Sites::Custom(CustomSite(idx))).collect()
+}
+
+#[cfg(test)]
+mod test {
+    use std::fs;
+
+    use super::{
+        FeedSubscription, Order, SiteDefinition, SiteInfo, SiteKind, Source, free_info, load_dir,
+        load_subscriptions, register, register_feed, save_subscription,
+    };
+    use crate::{Sites, Url};
+
+    fn info(id: &str) -> SiteInfo {
+        SiteInfo {
+            id: id.to_owned(),
+            name: format!("Comic {id}"),
+            homepage: "https://comic.example".to_owned(),
+            nsfw: false,
+        }
+    }
+
+    fn definition(id: &str, index_url: &str) -> SiteDefinition {
+        SiteDefinition {
+            info: info(id),
+            kind: SiteKind::Archive,
+            index_url: index_url.to_owned(),
+            list_selector: "ul.archive a".to_owned(),
+            link_attr: "href".to_owned(),
+            title_attr: None,
+            image_selector: "#comic img".to_owned(),
+            image_attr: "src".to_owned(),
+            url_prefix: None,
+            order: Order::NewestFirst,
+        }
+    }
+
+    #[test]
+    fn test_custom_load_dir() {
+        let dir = std::env::temp_dir().join(format!("daily-strip-sites-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(
+            dir.join("a.toml"),
+            r##"
+            id = "toml-comic"
+            name = "Toml Comic"
+            homepage = "https://toml.example"
+            index_url = "https://toml.example/archive"
+            list_selector = "ul.archive a"
+            title_attr = "title"
+            image_selector = "#comic img"
+            order = "oldest_first"
+            "##,
+        )
+        .unwrap();
+        fs::write(
+            dir.join("b.json"),
+            r##"{"id": "json-comic", "name": "Json Comic", "homepage": "https://json.example",
+            "index_url": "https://json.example/", "list_selector": "a.strip",
+            "image_selector": "img.strip", "nsfw": true}"##,
+        )
+        .unwrap();
+        // Broken selector, taken id, unknown format
+        let broken = r##"id = "broken"
+            name = "Broken"
+            homepage = "https://broken.example"
+            index_url = "https://broken.example"
+            list_selector = "ul >"
+            image_selector = "img""##;
+        fs::write(dir.join("c.toml"), broken).unwrap();
+        fs::write(dir.join("d.json"), fs::read(dir.join("b.json")).unwrap()).unwrap();
+        fs::write(dir.join("e.yaml"), "id: yaml").unwrap();
+        // Only archives need a list selector, the last file is skipped too
+        let wordpress = r##"id = "wordpress-comic"
+            name = "WordPress Comic"
+            homepage = "https://wordpress.example"
+            kind = "wordpress"
+            index_url = "https://wordpress.example/wp-json/wp/v2/comic"
+            image_selector = "#comic img""##;
+        fs::write(dir.join("f.toml"), wordpress).unwrap();
+        let archive = r##"id = "archive-comic"
+            name = "Archive Comic"
+            homepage = "https://archive.example"
+            index_url = "https://archive.example/archive"
+            image_selector = "#comic img""##;
+        fs::write(dir.join("g.toml"), archive).unwrap();
+
+        let (sources, skipped) = load_dir(&dir);
+        assert_eq!(skipped.len(), 4);
+        assert!(skipped.iter().all(|err| err.contains("daily-strip-sites-")));
+        let definitions: Vec<_> = sources
+            .into_iter()
+            .filter_map(|source| match source {
+                Source::Definition(definition) => Some(definition),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(definitions.len(), 3);
+        assert_eq!(definitions[0].title_attr.as_deref(), Some("title"));
+        assert_eq!(definitions[0].order, Order::OldestFirst);
+        assert_eq!(definitions[0].link_attr, "href");
+        assert_eq!(definitions[1].info.id, "json-comic");
+        assert!(definitions[1].info.nsfw);
+        assert_eq!(definitions[1].order, Order::NewestFirst);
+        assert_eq!(definitions[1].kind, SiteKind::Archive);
+        assert_eq!(definitions[2].kind, SiteKind::Wordpress);
+        let _ = fs::remove_dir_all(dir);
+    }
+
+    #[test]
+    fn test_custom_register() {
+        let site = register(definition(
+            "registered-comic",
+            "https://comic.example/archive",
+        ))
+        .unwrap();
+        assert_eq!(Sites::from_id("registered-comic"), Some(site));
+        assert_eq!(site.to_string(), "Comic registered-comic");
+        assert_eq!("Comic registered-comic".parse::<Sites>().unwrap(), site);
+        assert_eq!(site.fetch_url(), "https://comic.example/archive");
+        assert_eq!(site.homepage(), "comic.example");
+        assert!(Sites::sites_sorted().contains(&site));
+
+        let json = serde_json::to_string(&site).unwrap();
+        assert_eq!(serde_json::from_str::<Sites>(&json).unwrap(), site);
+
+        // Ids are unique, built-in ones included
+        assert!(register(definition("registered-comic", "https://comic.example")).is_err());
+        assert!(register(definition("xkcd", "https://comic.example")).is_err());
+    }
+
+    #[test]
+    fn test_custom_subscriptions() {
+        let path =
+            std::env::temp_dir().join(format!("daily-strip-feeds-{}.toml", std::process::id()));
+        assert!(load_subscriptions(&path).unwrap().is_empty());
+
+        let feed = |id: &str| FeedSubscription {
+            info: info(id),
+            url: format!("https://comic.example/{id}.xml"),
+        };
+        save_subscription(&path, feed("first-feed")).unwrap();
+        save_subscription(&path, feed("second-feed")).unwrap();
+        assert_eq!(
+            load_subscriptions(&path).unwrap(),
+            [feed("first-feed"), feed("second-feed")]
+        );
+        let _ = fs::remove_file(path);
+
+        let site = register_feed(feed("registered-feed")).unwrap();
+        assert_eq!(
+            super::feed_site("https://comic.example/registered-feed.xml"),
+            Some(site)
+        );
+        assert_eq!(
+            site.fetch_url(),
+            "https://comic.example/registered-feed.xml"
+        );
+    }
+
+    #[test]
+    fn test_custom_free_info() {
+        let info = free_info("Dinosaur Comics", "https://qwantz.example".to_owned());
+        assert_eq!(info.id, "dinosaur-comics-2");
+        assert_eq!(info.name, "Dinosaur Comics (2)");
+        assert_eq!(free_info("A  new Comic", String::new()).id, "a-new-comic");
+        assert_eq!(free_info("???", String::new()).id, "feed");
+    }
+}
@@ -0,0 +1,92 @@
+use std::{fs::File, io::Write};
+
+use anyhow::Result;
+use zip::{CompressionMethod, ZipWriter, write::SimpleFileOptions};
+
+use super::{PageWriter, image_extension, range_title};
+use crate::{Sites, Strip, Url, cache::ImageData, escape_xml};
+
+/// Comic book archive: the images in reading order plus a ComicInfo.xml
+pub(super) struct CbzWriter {
+    zip: ZipWriter<File>,
+    site: Sites,
+    /// Digits of the page names, so that they sort in reading order
+    width: usize,
+    pages: usize,
+    /// First page and title of each strip
+    strips: Vec<(usize, String)>,
+}
+
+impl CbzWriter {
+    pub(super) fn new(file: File, site: Sites, total: usize) -> Self {
+        Self {
+            zip: ZipWriter::new(file),
+            site,
+            width: total.to_string().len().max(3),
+            pages: 0,
+            strips: Vec::with_capacity(total),
+        }
+    }
+
+    fn comic_info(&self) -> String {
+        let pages: String = self
+            .strips
+            .iter()
+            .map(|(pos, title)| {
+                format!(
+                    "    <Page Image=\"{pos}\" Bookmark=\"{}\" />\n",
+                    escape_xml(title)
+                )
+            })
+            .collect();
+        let titles: Vec<_> = self.strips.iter().map(|(_, title)| title.clone()).collect();
+        let title = range_title(&titles);
+
+        format!(
+            r#"<?xml version="1.0" encoding="utf-8"?>
+<ComicInfo xmlns:xsi="http://www.w3.org/2001/XMLSchema-instance" xmlns:xsd="http://www.w3.org/2001/XMLSchema">
+  <Title>{}</Title>
+  <Series>{}</Series>
+  <Web>https://{}</Web>
+  <PageCount>{}</PageCount>
+  <Pages>
+{pages}  </Pages>
+</ComicInfo>
+"#,
+            escape_xml(&title),
+            escape_xml(&self.site.to_string()),
+            self.site.homepage(),
+            self.pages,
+        )
+    }
+}
+
+impl PageWriter for CbzWriter {
+    fn add_strip(&mut self, strip: &Strip, images: &[ImageData]) -> Result<()> {
+        let number = format!("{:0width$}", self.strips.len() + 1, width = self.width);
+        self.strips.push((self.pages, strip.title.clone()));
+        for (pos, image) in images.iter().enumerate() {
+            // `001.png` then `001_2.png`, which sort before `002.png`
+            let name = match pos {
+                0 => format!("{number}.{}", image_extension(strip, image)),
+                _ => format!("{number}_{}.{}", pos + 1, image_extension(strip, image)),
+            };
+            // Images are compressed already
+            let options =
+                SimpleFileOptions::default().compression_method(CompressionMethod::Stored);
+            self.zip.start_file(name, options)?;
+            self.zip.write_all(image)?;
+            self.pages += 1;
+        }
+        Ok(())
+    }
+
+    fn finish(mut self: Box<Self>) -> Result<()> {
+        let info = self.comic_info();
+        self.zip
+            .start_file("ComicInfo.xml", SimpleFileOptions::default())?;
+        self.zip.write_all(info.as_bytes())?;
+        self.zip.finish()?;
+        Ok(())
+    }
+}
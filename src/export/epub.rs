@@ -0,0 +1,248 @@
+use std::{
+    fs::File,
+    io::{Cursor, Write},
+};
+
+use anyhow::Result;
+use chrono::Utc;
+use image::ImageFormat;
+use zip::{CompressionMethod, ZipWriter, write::SimpleFileOptions};
+
+use super::{InvalidImage, PageWriter, range_title};
+use crate::{Sites, Strip, Url, cache::ImageData, escape_xml};
+
+const CONTAINER: &str = r#"<?xml version="1.0" encoding="utf-8"?>
+<container version="1.0" xmlns="urn:oasis:names:tc:opendocument:xmlns:container">
+  <rootfiles>
+    <rootfile full-path="OEBPS/content.opf" media-type="application/oebps-package+xml"/>
+  </rootfiles>
+</container>
+"#;
+
+struct Page {
+    title: String,
+    idx: usize,
+    /// Path and media type of each image, in reading order
+    images: Vec<(String, &'static str)>,
+}
+
+/// EPUB 3 book, one strip per page, with both a navigation document and a
+/// NCX table of contents for older readers
+pub(super) struct EpubWriter {
+    zip: ZipWriter<File>,
+    site: Sites,
+    pages: Vec<Page>,
+}
+
+impl EpubWriter {
+    pub(super) fn new(file: File, site: Sites) -> Result<Self> {
+        let mut zip = ZipWriter::new(file);
+        // The mimetype has to come first and uncompressed
+        let stored = SimpleFileOptions::default().compression_method(CompressionMethod::Stored);
+        zip.start_file("mimetype", stored)?;
+        zip.write_all(b"application/epub+zip")?;
+        zip.start_file("META-INF/container.xml", SimpleFileOptions::default())?;
+        zip.write_all(CONTAINER.as_bytes())?;
+
+        Ok(Self {
+            zip,
+            site,
+            pages: Vec::new(),
+        })
+    }
+
+    fn write(&mut self, name: &str, content: &str) -> Result<()> {
+        self.zip
+            .start_file(format!("OEBPS/{name}"), SimpleFileOptions::default())?;
+        self.zip.write_all(content.as_bytes())?;
+        Ok(())
+    }
+
+    fn identifier(&self) -> String {
+        let first = self.pages.first().map(|page| page.idx).unwrap_or_default();
+        let last = self.pages.last().map(|page| page.idx).unwrap_or_default();
+        format!("urn:daily-strip:{}:{first}-{last}", self.site.id())
+    }
+
+    fn title(&self) -> String {
+        let titles: Vec<_> = self.pages.iter().map(|page| page.title.clone()).collect();
+        format!("{}: {}", self.site, range_title(&titles))
+    }
+
+    fn package(&self) -> String {
+        let mut manifest = String::new();
+        let mut spine = String::new();
+        for (pos, page) in self.pages.iter().enumerate() {
+            let n = pos + 1;
+            manifest += &format!(
+                "    <item id=\"page-{n}\" href=\"page-{n}.xhtml\" media-type=\"application/xhtml+xml\"/>\n"
+            );
+            for (pos, (image, media_type)) in page.images.iter().enumerate() {
+                let id = match pos {
+                    0 => format!("image-{n}"),
+                    _ => format!("image-{n}-{}", pos + 1),
+                };
+                manifest += &format!(
+                    "    <item id=\"{id}\" href=\"{image}\" media-type=\"{media_type}\"/>\n"
+                );
+            }
+            spine += &format!("    <itemref idref=\"page-{n}\"/>\n");
+        }
+
+        format!(
+            r#"<?xml version="1.0" encoding="utf-8"?>
+<package xmlns="http://www.idpf.org/2007/opf" version="3.0" unique-identifier="id">
+  <metadata xmlns:dc="http://purl.org/dc/elements/1.1/">
+    <dc:identifier id="id">{}</dc:identifier>
+    <dc:title>{}</dc:title>
+    <dc:source>https://{}</dc:source>
+    <dc:language>en</dc:language>
+    <meta property="dcterms:modified">{}</meta>
+  </metadata>
+  <manifest>
+    <item id="nav" href="nav.xhtml" media-type="application/xhtml+xml" properties="nav"/>
+    <item id="ncx" href="toc.ncx" media-type="application/x-dtbncx+xml"/>
+{manifest}  </manifest>
+  <spine toc="ncx">
+{spine}  </spine>
+</package>
+"#,
+            escape_xml(&self.identifier()),
+            escape_xml(&self.title()),
+            self.site.homepage(),
+            Utc::now().format("%Y-%m-%dT%H:%M:%SZ"),
+        )
+    }
+
+    fn nav(&self) -> String {
+        let entries: String = self
+            .pages
+            .iter()
+            .enumerate()
+            .map(|(pos, page)| {
+                format!(
+                    "        <li><a href=\"page-{}.xhtml\">{}</a></li>\n",
+                    pos + 1,
+                    escape_xml(&page.title)
+                )
+            })
+            .collect();
+
+        format!(
+            r#"<?xml version="1.0" encoding="utf-8"?>
+<!DOCTYPE html>
+<html xmlns="http://www.w3.org/1999/xhtml" xmlns:epub="http://www.idpf.org/2007/ops" lang="en">
+  <head><title>Contents</title></head>
+  <body>
+    <nav epub:type="toc" id="toc">
+      <h1>Contents</h1>
+      <ol>
+{entries}      </ol>
+    </nav>
+  </body>
+</html>
+"#
+        )
+    }
+
+    fn ncx(&self) -> String {
+        let points: String = self
+            .pages
+            .iter()
+            .enumerate()
+            .map(|(pos, page)| {
+                let n = pos + 1;
+                format!(
+                    "    <navPoint id=\"nav-{n}\" playOrder=\"{n}\"><navLabel><text>{}</text></navLabel><content src=\"page-{n}.xhtml\"/></navPoint>\n",
+                    escape_xml(&page.title)
+                )
+            })
+            .collect();
+
+        format!(
+            r#"<?xml version="1.0" encoding="utf-8"?>
+<ncx xmlns="http://www.daisy.org/z3986/2005/ncx/" version="2005-1">
+  <head><meta name="dtb:uid" content="{}"/></head>
+  <docTitle><text>{}</text></docTitle>
+  <navMap>
+{points}  </navMap>
+</ncx>
+"#,
+            escape_xml(&self.identifier()),
+            escape_xml(&self.title()),
+        )
+    }
+}
+
+impl PageWriter for EpubWriter {
+    fn add_strip(&mut self, strip: &Strip, images: &[ImageData]) -> Result<()> {
+        let n = self.pages.len() + 1;
+        let mut page = Page {
+            title: strip.title.clone(),
+            idx: strip.idx,
+            images: Vec::with_capacity(images.len()),
+        };
+        let images = images
+            .iter()
+            .map(|image| core_image(image).map_err(InvalidImage))
+            .collect::<Result<Vec<_>, _>>()?;
+        for (pos, (data, format)) in images.into_iter().enumerate() {
+            let ext = format.extensions_str()[0];
+            let path = match pos {
+                0 => format!("images/{n}.{ext}"),
+                _ => format!("images/{n}_{}.{ext}", pos + 1),
+            };
+            let stored = SimpleFileOptions::default().compression_method(CompressionMethod::Stored);
+            self.zip.start_file(format!("OEBPS/{path}"), stored)?;
+            self.zip.write_all(&data)?;
+            page.images.push((path, format.to_mime_type()));
+        }
+
+        let title = escape_xml(&page.title);
+        let images: String = page
+            .images
+            .iter()
+            .map(|(path, _)| format!("    <img src=\"{path}\" alt=\"{title}\"/>\n"))
+            .collect();
+        let xhtml = format!(
+            r#"<?xml version="1.0" encoding="utf-8"?>
+<!DOCTYPE html>
+<html xmlns="http://www.w3.org/1999/xhtml" lang="en">
+  <head>
+    <title>{title}</title>
+    <style>body {{ margin: 0; text-align: center; }} img {{ max-width: 100%; max-height: 95vh; }}</style>
+  </head>
+  <body>
+    <h2>{title}</h2>
+{images}  </body>
+</html>
+"#
+        );
+        self.write(&format!("page-{n}.xhtml"), &xhtml)?;
+        self.pages.push(page);
+        Ok(())
+    }
+
+    fn finish(mut self: Box<Self>) -> Result<()> {
+        let (package, nav, ncx) = (self.package(), self.nav(), self.ncx());
+        self.write("content.opf", &package)?;
+        self.write("nav.xhtml", &nav)?;
+        self.write("toc.ncx", &ncx)?;
+        self.zip.finish()?;
+        Ok(())
+    }
+}
+
+/// Images in a format every reader supports, others are converted to PNG
+fn core_image(image: &[u8]) -> Result<(Vec<u8>, ImageFormat)> {
+    match image::guess_format(image) {
+        Ok(
+            format @ (ImageFormat::Png | ImageFormat::Jpeg | ImageFormat::Gif | ImageFormat::WebP),
+        ) => Ok((image.to_vec(), format)),
+        _ => {
+            let mut png = Cursor::new(Vec::new());
+            image::load_from_memory(image)?.write_to(&mut png, ImageFormat::Png)?;
+            Ok((png.into_inner(), ImageFormat::Png))
+        }
+    }
+}
@@ -0,0 +1,475 @@
+mod cbz;
+mod epub;
+mod pdf;
+
+use std::{fs::File, ops::RangeInclusive, path::Path};
+
+use anyhow::{Result, bail};
+use strum_macros::{Display, EnumString};
+use thiserror::Error;
+use tokio::{
+    sync::mpsc::{Receiver, channel},
+    task::spawn_blocking,
+};
+
+use crate::{
+    Fetcher, Sites, Strip,
+    cache::{ImageCache, ImageData},
+};
+
+/// Strips to export, indexes count from the oldest strip
+#[derive(Debug, Clone, Default, Hash, PartialEq, Eq)]
+pub enum ExportRange {
+    #[default]
+    All,
+    Range(RangeInclusive<usize>),
+}
+
+#[derive(Debug, Clone, Copy, Default, Hash, PartialEq, Eq, Display, EnumString)]
+#[strum(serialize_all = "lowercase")]
+pub enum ExportFormat {
+    #[default]
+    Cbz,
+    Epub,
+    Pdf,
+}
+
+impl ExportFormat {
+    pub fn extension(&self) -> &'static str {
+        match self {
+            ExportFormat::Cbz => "cbz",
+            ExportFormat::Epub => "epub",
+            ExportFormat::Pdf => "pdf",
+        }
+    }
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct ExportReport {
+    pub total: usize,
+    pub exported: usize,
+    /// Left out of the export, their page or an image could not be fetched
+    pub failed: usize,
+}
+
+/// Strips loaded ahead of the writer, at most
+const PENDING_STRIPS: usize = 4;
+
+/// Image the format cannot hold, its strip is left out rather than failing the export
+#[derive(Debug, Error)]
+#[error("undecodable image: {0}")]
+struct InvalidImage(anyhow::Error);
+
+/// Receives the pages of an export, oldest strip first
+trait PageWriter: Send {
+    /// Add the strip, `images` holding every image of it in reading order.
+    ///
+    /// Fails with [`InvalidImage`] before writing anything when an image cannot be decoded.
+    fn add_strip(&mut self, strip: &Strip, images: &[ImageData]) -> Result<()>;
+    fn finish(self: Box<Self>) -> Result<()>;
+}
+
+/// Write the strips in `range` to `path`, leaving out those failing to load.
+///
+/// `progress` is called with the strips done and the total after each one. Nothing is left at
+/// `path` when the export fails.
+pub async fn export<F: Fetcher + ?Sized>(
+    fetcher: &F,
+    range: &ExportRange,
+    format: ExportFormat,
+    path: &Path,
+    images: &ImageCache,
+    progress: impl FnMut(usize, usize) + Send,
+) -> Result<ExportReport> {
+    let last = fetcher.last().await?;
+    let range = match range {
+        ExportRange::All => 0..=last.idx,
+        ExportRange::Range(range) => *range.start()..=(*range.end()).min(last.idx),
+    };
+    if range.is_empty() {
+        bail!("no strip in range, the archive goes from 0 to {}", last.idx);
+    }
+
+    let res = write_export(fetcher, range, &last, format, path, images, progress).await;
+    if res.is_err() {
+        let _ = tokio::fs::remove_file(path).await;
+    }
+    res
+}
+
+async fn write_export<F: Fetcher + ?Sized>(
+    fetcher: &F,
+    range: RangeInclusive<usize>,
+    last: &Strip,
+    format: ExportFormat,
+    path: &Path,
+    images: &ImageCache,
+    mut progress: impl FnMut(usize, usize) + Send,
+) -> Result<ExportReport> {
+    let total = range.end() - range.start() + 1;
+    // Encoding and writing block, they are left to a thread of their own
+    let (pages, pending) = channel(PENDING_STRIPS);
+    let (site, path) = (last.site, path.to_owned());
+    let writer = spawn_blocking(move || write_pages(pending, format, site, &path, total));
+
+    let mut failed = 0;
+    progress(0, total);
+    for (done, idx) in range.enumerate() {
+        match load_strip(fetcher, idx, last, images).await {
+            Ok(page) => {
+                // The writer gave up, its error tells why
+                if pages.send(page).await.is_err() {
+                    break;
+                }
+            }
+            Err(_) => failed += 1,
+        }
+        progress(done + 1, total);
+    }
+    drop(pages);
+
+    let (exported, invalid) = writer.await??;
+    Ok(ExportReport {
+        total,
+        exported,
+        failed: failed + invalid,
+    })
+}
+
+/// Write the strips received to `path`, returning how many were exported and left out
+fn write_pages(
+    mut pending: Receiver<(Strip, Vec<ImageData>)>,
+    format: ExportFormat,
+    site: Sites,
+    path: &Path,
+    total: usize,
+) -> Result<(usize, usize)> {
+    let file = File::create(path)?;
+    let mut writer: Box<dyn PageWriter> = match format {
+        ExportFormat::Cbz => Box::new(cbz::CbzWriter::new(file, site, total)),
+        ExportFormat::Epub => Box::new(epub::EpubWriter::new(file, site)?),
+        ExportFormat::Pdf => Box::new(pdf::PdfWriter::new(file, site)?),
+    };
+
+    let (mut exported, mut invalid) = (0, 0);
+    while let Some((strip, data)) = pending.blocking_recv() {
+        match writer.add_strip(&strip, &data) {
+            Ok(_) => exported += 1,
+            Err(err) if err.is::<InvalidImage>() => invalid += 1,
+            Err(err) => return Err(err),
+        }
+    }
+    if exported == 0 {
+        bail!("none of the {total} strips could be loaded");
+    }
+    writer.finish()?;
+    Ok((exported, invalid))
+}
+
+/// Strip at `idx` along with every image of it
+async fn load_strip<F: Fetcher + ?Sized>(
+    fetcher: &F,
+    idx: usize,
+    last: &Strip,
+    images: &ImageCache,
+) -> Result<(Strip, Vec<ImageData>)> {
+    let strip = match idx == last.idx {
+        true => last.clone(),
+        false => fetcher.get(idx).await?,
+    };
+    let mut data = Vec::new();
+    for url in strip.image_urls() {
+        data.push(images.get(url).await?);
+    }
+    Ok((strip, data))
+}
+
+/// Title of a book made of the strips titled `titles`
+fn range_title(titles: &[String]) -> String {
+    match (titles.first(), titles.last()) {
+        (Some(first), Some(last)) if first != last => format!("{first} - {last}"),
+        (Some(first), _) => first.clone(),
+        _ => String::new(),
+    }
+}
+
+/// File extension of the image, from its content or else from its url
+pub(crate) fn image_extension(strip: &Strip, image: &[u8]) -> String {
+    match image::guess_format(image) {
+        Ok(format) => format.extensions_str()[0].to_owned(),
+        Err(_) => strip
+            .url
+            .rsplit_once('.')
+            .map(|(_, ext)| ext.to_lowercase())
+            .filter(|ext| ext.len() <= 4 && ext.chars().all(|c| c.is_ascii_alphanumeric()))
+            .unwrap_or_else(|| "img".to_owned()),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::{fs, io::Read, path::Path};
+
+    use zip::ZipArchive;
+
+    use super::{ExportFormat, ExportRange, export};
+    use crate::{
+        Fetcher, Sites,
+        cache::ImageCache,
+        fetcher::{build_fetcher_with_base_url, build_local_fetcher, fixture::FixtureServer},
+        mirror::{MANIFEST, MirrorEntry},
+    };
+
+    async fn export_fixture(image: &str, format: ExportFormat) -> (Vec<u8>, Vec<(usize, usize)>) {
+        let fixture = format!("../images/{image}");
+        let routes = [
+            ("/archive.php", "archive.html"),
+            ("/index.php", "comic.html"),
+            ("/comics/*", fixture.as_str()),
+        ];
+        let server = FixtureServer::start("dinosaur_comics", &routes).await;
+        let fetcher = build_fetcher_with_base_url(Sites::DinosaurComics, server.url())
+            .await
+            .unwrap();
+        let path = std::env::temp_dir().join(format!(
+            "daily-strip-{}-{image}.{}",
+            std::process::id(),
+            format.extension()
+        ));
+        let images = ImageCache::new(1024 * 1024, None);
+
+        let mut updates = Vec::new();
+        let report = export(
+            &fetcher,
+            &ExportRange::All,
+            format,
+            &path,
+            &images,
+            |done, total| updates.push((done, total)),
+        )
+        .await
+        .unwrap();
+        assert_eq!((report.exported, report.failed), (2, 0));
+        assert_no_range(&fetcher, &path, &images).await;
+
+        let data = fs::read(&path).unwrap();
+        let _ = fs::remove_file(path);
+        (data, updates)
+    }
+
+    async fn assert_no_range(fetcher: &impl Fetcher, path: &Path, images: &ImageCache) {
+        let range = ExportRange::Range(5..=9);
+        let res = export(fetcher, &range, ExportFormat::Cbz, path, images, |_, _| {}).await;
+        assert!(res.is_err());
+    }
+
+    fn read_entry(archive: &mut ZipArchive<std::io::Cursor<Vec<u8>>>, name: &str) -> String {
+        let mut content = String::new();
+        archive
+            .by_name(name)
+            .unwrap()
+            .read_to_string(&mut content)
+            .unwrap();
+        content
+    }
+
+    #[tokio::test]
+    async fn test_export_cbz() {
+        let (data, updates) = export_fixture("strip.png", ExportFormat::Cbz).await;
+        assert_eq!(updates, [(0, 2), (1, 2), (2, 2)]);
+
+        let mut cbz = ZipArchive::new(std::io::Cursor::new(data)).unwrap();
+        let names: Vec<_> = cbz.file_names().collect();
+        assert_eq!(names, ["001.png", "002.png", "ComicInfo.xml"]);
+        let info = read_entry(&mut cbz, "ComicInfo.xml");
+        assert!(info.contains("<Series>Dinosaur Comics</Series>"));
+        assert!(info.contains("<PageCount>2</PageCount>"));
+    }
+
+    #[tokio::test]
+    async fn test_export_epub() {
+        let (data, _) = export_fixture("strip.jpg", ExportFormat::Epub).await;
+        assert_eq!(&data[30..38], b"mimetype");
+
+        let mut epub = ZipArchive::new(std::io::Cursor::new(data)).unwrap();
+        assert!(epub.by_name("OEBPS/images/2.jpg").is_ok());
+        let nav = read_entry(&mut epub, "OEBPS/nav.xhtml");
+        assert_eq!(nav.matches("<li><a href=\"page-").count(), 2);
+        let package = read_entry(&mut epub, "OEBPS/content.opf");
+        assert!(package.contains(r#"href="images/1.jpg" media-type="image/jpeg""#));
+        assert!(package.contains("urn:daily-strip:dinosaur-comics:0-1"));
+    }
+
+    #[tokio::test]
+    async fn test_export_pdf() {
+        for image in ["strip.png", "strip.jpg"] {
+            let (data, _) = export_fixture(image, ExportFormat::Pdf).await;
+            let text = String::from_utf8_lossy(&data);
+            assert!(text.starts_with("%PDF-1.4"));
+            assert!(text.contains("/Type /Pages /Kids [6 0 R 9 0 R] /Count 2"));
+            assert!(text.contains("/Type /Outlines /First 10 0 R /Last 11 0 R /Count 2"));
+
+            // Every object is where the cross-reference table says
+            let (_, startxref) = text.rsplit_once("startxref\n").unwrap();
+            let xref: usize = startxref.lines().next().unwrap().parse().unwrap();
+            let table = &text[xref..];
+            for (id, line) in table
+                .lines()
+                .skip(3)
+                .take_while(|l| l.ends_with(" n "))
+                .enumerate()
+            {
+                let offset: usize = line[..10].parse().unwrap();
+                assert!(text[offset..].starts_with(&format!("{} 0 obj", id + 1)));
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn test_export_multi_image() {
+        let dir = std::env::temp_dir().join(format!("daily-strip-multi-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let image = fs::read("tests/fixtures/images/strip.png").unwrap();
+        let mut manifest = String::new();
+        for (idx, extra_files) in [(0, vec!["00000_2.png"]), (1, vec![])] {
+            let entry = MirrorEntry {
+                idx,
+                title: format!("strip {idx}"),
+                page: None,
+                image: String::new(),
+                file: format!("{idx:05}.png"),
+                sha256: String::new(),
+                alt_text: None,
+                hover_text: None,
+                published: None,
+                extra_files: extra_files.into_iter().map(str::to_owned).collect(),
+                extra_sha256: Vec::new(),
+            };
+            for file in std::iter::once(&entry.file).chain(&entry.extra_files) {
+                fs::write(dir.join(file), &image).unwrap();
+            }
+            manifest += &(serde_json::to_string(&entry).unwrap() + "\n");
+        }
+        fs::write(dir.join(MANIFEST), manifest).unwrap();
+        let fetcher = build_local_fetcher(Sites::Oglaf, &dir).await.unwrap();
+        let images = ImageCache::new(0, None).with_local_dir(dir.clone());
+
+        let path = dir.join("export.cbz");
+        let report = export(
+            &fetcher,
+            &ExportRange::All,
+            ExportFormat::Cbz,
+            &path,
+            &images,
+            |_, _| {},
+        )
+        .await
+        .unwrap();
+        assert_eq!(report.exported, 2);
+        let mut cbz = ZipArchive::new(std::io::Cursor::new(fs::read(&path).unwrap())).unwrap();
+        let names: Vec<_> = cbz.file_names().collect();
+        assert_eq!(names, ["001.png", "001_2.png", "002.png", "ComicInfo.xml"]);
+        let info = read_entry(&mut cbz, "ComicInfo.xml");
+        assert!(info.contains("<PageCount>3</PageCount>"));
+        assert!(info.contains(r#"<Page Image="2" Bookmark="strip 1" />"#));
+
+        let path = dir.join("export.pdf");
+        export(
+            &fetcher,
+            &ExportRange::All,
+            ExportFormat::Pdf,
+            &path,
+            &images,
+            |_, _| {},
+        )
+        .await
+        .unwrap();
+        let text = String::from_utf8_lossy(&fs::read(&path).unwrap()).into_owned();
+        assert!(text.contains("/Count 3 >>"));
+        assert!(text.contains("/Type /Outlines /First 13 0 R /Last 14 0 R /Count 2"));
+
+        let _ = fs::remove_dir_all(dir);
+    }
+
+    #[tokio::test]
+    async fn test_export_failures() {
+        let dir = std::env::temp_dir().join(format!("daily-strip-holes-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let image = fs::read("tests/fixtures/images/strip.png").unwrap();
+        // Strip 1 never made it to the mirror, the image of strip 2 went missing
+        let mut manifest = String::new();
+        for idx in [0, 2, 3] {
+            let entry = MirrorEntry {
+                idx,
+                title: format!("strip {idx}"),
+                page: None,
+                image: String::new(),
+                file: format!("{idx:05}.png"),
+                sha256: String::new(),
+                alt_text: None,
+                hover_text: None,
+                published: None,
+                extra_files: Vec::new(),
+                extra_sha256: Vec::new(),
+            };
+            if idx != 2 {
+                fs::write(dir.join(&entry.file), &image).unwrap();
+            }
+            manifest += &(serde_json::to_string(&entry).unwrap() + "\n");
+        }
+        fs::write(dir.join(MANIFEST), manifest).unwrap();
+        let fetcher = build_local_fetcher(Sites::Oglaf, &dir).await.unwrap();
+        let images = ImageCache::new(0, None).with_local_dir(dir.clone());
+
+        let path = dir.join("export.cbz");
+        let mut updates = Vec::new();
+        let report = export(
+            &fetcher,
+            &ExportRange::All,
+            ExportFormat::Cbz,
+            &path,
+            &images,
+            |done, total| updates.push((done, total)),
+        )
+        .await
+        .unwrap();
+        assert_eq!((report.total, report.exported, report.failed), (4, 2, 2));
+        assert_eq!(updates.last(), Some(&(4, 4)));
+        let cbz = ZipArchive::new(std::io::Cursor::new(fs::read(&path).unwrap())).unwrap();
+        assert_eq!(cbz.file_names().count(), 3);
+
+        // Images the format cannot decode leave their strip out too
+        fs::write(dir.join("00003.png"), "<html>Not found</html>").unwrap();
+        for format in [ExportFormat::Epub, ExportFormat::Pdf] {
+            let path = dir.join(format!("export.{}", format.extension()));
+            let report = export(
+                &fetcher,
+                &ExportRange::All,
+                format,
+                &path,
+                &images,
+                |_, _| {},
+            )
+            .await
+            .unwrap();
+            assert_eq!((report.exported, report.failed), (1, 3));
+        }
+
+        // Nothing to export, no file is left behind
+        let path = dir.join("empty.pdf");
+        let range = ExportRange::Range(1..=2);
+        let res = export(
+            &fetcher,
+            &range,
+            ExportFormat::Pdf,
+            &path,
+            &images,
+            |_, _| {},
+        )
+        .await;
+        assert!(res.is_err());
+        assert!(!path.exists());
+
+        let _ = fs::remove_dir_all(dir);
+    }
+}
@@ -0,0 +1,227 @@
+use std::{
+    fs::File,
+    io::{BufWriter, Cursor, Write},
+};
+
+use anyhow::Result;
+use flate2::{Compression, write::ZlibEncoder};
+use image::{ColorType, ImageDecoder, ImageFormat, codecs::jpeg::JpegDecoder};
+
+use super::{InvalidImage, PageWriter, range_title};
+use crate::{Sites, Strip, cache::ImageData};
+
+/// Width of every page in points, the one of an A4 sheet
+const PAGE_WIDTH: f32 = 595.0;
+
+/// Objects written last, once every page is known
+const CATALOG: usize = 1;
+const PAGES: usize = 2;
+const OUTLINES: usize = 3;
+
+/// PDF with one strip per page, the titles being the outline (bookmarks).
+///
+/// Objects are streamed as pages come, the cross-reference table is written on finish.
+pub(super) struct PdfWriter {
+    out: BufWriter<File>,
+    site: Sites,
+    written: usize,
+    /// Offset of each object, the id being the position plus one
+    offsets: Vec<usize>,
+    /// Page objects, one per image
+    pages: Vec<usize>,
+    /// First page object and title of each strip
+    strips: Vec<(usize, String)>,
+}
+
+impl PdfWriter {
+    pub(super) fn new(file: File, site: Sites) -> Result<Self> {
+        let mut writer = Self {
+            out: BufWriter::new(file),
+            site,
+            written: 0,
+            offsets: vec![0; OUTLINES],
+            pages: Vec::new(),
+            strips: Vec::new(),
+        };
+        // The binary comment marks the file as binary to transfer tools
+        writer.write(b"%PDF-1.4\n%\xe2\xe3\xcf\xd3\n")?;
+        Ok(writer)
+    }
+
+    fn write(&mut self, data: &[u8]) -> Result<()> {
+        self.out.write_all(data)?;
+        self.written += data.len();
+        Ok(())
+    }
+
+    fn alloc(&mut self) -> usize {
+        self.offsets.push(0);
+        self.offsets.len()
+    }
+
+    fn object(&mut self, id: usize, dict: &str) -> Result<()> {
+        self.offsets[id - 1] = self.written;
+        self.write(format!("{id} 0 obj\n{dict}\nendobj\n").as_bytes())
+    }
+
+    /// `entries` of the stream dictionary, `/Length` excluded
+    fn stream(&mut self, id: usize, entries: &str, data: &[u8]) -> Result<()> {
+        self.offsets[id - 1] = self.written;
+        let dict = format!("<< {entries} /Length {} >>", data.len());
+        self.write(format!("{id} 0 obj\n{dict}\nstream\n").as_bytes())?;
+        self.write(data)?;
+        self.write(b"\nendstream\nendobj\n")
+    }
+
+    /// Page showing `image` over the whole width, returning the page object
+    fn add_page(&mut self, image: PdfImage) -> Result<usize> {
+        let (width, height, filter, data) = image;
+        let page_height = PAGE_WIDTH * height as f32 / width as f32;
+        let (image_id, content_id, page_id) = (self.alloc(), self.alloc(), self.alloc());
+
+        self.stream(
+            image_id,
+            &format!(
+                "/Type /XObject /Subtype /Image /Width {width} /Height {height} /BitsPerComponent 8 {filter}"
+            ),
+            &data,
+        )?;
+        let content = format!("q {PAGE_WIDTH} 0 0 {page_height:.2} 0 0 cm /Im0 Do Q");
+        self.stream(content_id, "", content.as_bytes())?;
+        self.object(
+            page_id,
+            &format!(
+                "<< /Type /Page /Parent {PAGES} 0 R /MediaBox [0 0 {PAGE_WIDTH} {page_height:.2}] /Resources << /XObject << /Im0 {image_id} 0 R >> >> /Contents {content_id} 0 R >>"
+            ),
+        )?;
+        self.pages.push(page_id);
+        Ok(page_id)
+    }
+}
+
+impl PageWriter for PdfWriter {
+    fn add_strip(&mut self, strip: &Strip, images: &[ImageData]) -> Result<()> {
+        let images = images
+            .iter()
+            .map(|image| pdf_image(image).map_err(InvalidImage))
+            .collect::<Result<Vec<_>, _>>()?;
+        let mut first = None;
+        for image in images {
+            let page_id = self.add_page(image)?;
+            first.get_or_insert(page_id);
+        }
+        if let Some(page_id) = first {
+            self.strips.push((page_id, strip.title.clone()));
+        }
+        Ok(())
+    }
+
+    fn finish(mut self: Box<Self>) -> Result<()> {
+        let strips = std::mem::take(&mut self.strips);
+        let items: Vec<_> = strips.iter().map(|_| self.alloc()).collect();
+        for (pos, ((page_id, title), id)) in strips.iter().zip(&items).enumerate() {
+            let mut dict = format!(
+                "<< /Title {} /Parent {OUTLINES} 0 R /Dest [{page_id} 0 R /Fit]",
+                pdf_text(title)
+            );
+            if let Some(prev) = pos.checked_sub(1).map(|pos| items[pos]) {
+                dict += &format!(" /Prev {prev} 0 R");
+            }
+            if let Some(next) = items.get(pos + 1) {
+                dict += &format!(" /Next {next} 0 R");
+            }
+            self.object(*id, &(dict + " >>"))?;
+        }
+
+        let outlines = match (items.first(), items.last()) {
+            (Some(first), Some(last)) => format!(
+                "<< /Type /Outlines /First {first} 0 R /Last {last} 0 R /Count {} >>",
+                items.len()
+            ),
+            _ => "<< /Type /Outlines /Count 0 >>".to_owned(),
+        };
+        self.object(OUTLINES, &outlines)?;
+
+        let kids: Vec<_> = self.pages.iter().map(|id| format!("{id} 0 R")).collect();
+        self.object(
+            PAGES,
+            &format!(
+                "<< /Type /Pages /Kids [{}] /Count {} >>",
+                kids.join(" "),
+                kids.len()
+            ),
+        )?;
+        self.object(
+            CATALOG,
+            &format!(
+                "<< /Type /Catalog /Pages {PAGES} 0 R /Outlines {OUTLINES} 0 R /PageMode /UseOutlines >>"
+            ),
+        )?;
+
+        let titles: Vec<_> = strips.into_iter().map(|(_, title)| title).collect();
+        let info = self.alloc();
+        let title = format!("{}: {}", self.site, range_title(&titles));
+        self.object(
+            info,
+            &format!("<< /Title {} /Producer (daily_strip) >>", pdf_text(&title)),
+        )?;
+
+        let xref = self.written;
+        let mut table = format!("xref\n0 {}\n0000000000 65535 f \n", self.offsets.len() + 1);
+        for offset in &self.offsets {
+            table += &format!("{offset:010} 00000 n \n");
+        }
+        table += &format!(
+            "trailer\n<< /Size {} /Root {CATALOG} 0 R /Info {info} 0 R >>\nstartxref\n{xref}\n%%EOF\n",
+            self.offsets.len() + 1
+        );
+        self.write(table.as_bytes())?;
+        self.out.flush()?;
+        Ok(())
+    }
+}
+
+/// Size, filter entries and data of an image
+type PdfImage = (u32, u32, String, Vec<u8>);
+
+/// Image as embedded in the document, JPEG files are embedded as they are
+fn pdf_image(image: &[u8]) -> Result<PdfImage> {
+    if let Ok(ImageFormat::Jpeg) = image::guess_format(image) {
+        let decoder = JpegDecoder::new(Cursor::new(image))?;
+        let (width, height) = decoder.dimensions();
+        let color_space = match decoder.color_type() {
+            ColorType::L8 => Some("DeviceGray"),
+            ColorType::Rgb8 => Some("DeviceRGB"),
+            _ => None,
+        };
+        if let Some(color_space) = color_space {
+            let filter = format!("/ColorSpace /{color_space} /Filter /DCTDecode");
+            return Ok((width, height, filter, image.to_vec()));
+        }
+    }
+
+    // Anything else is flattened on a white background and deflated
+    let rgba = image::load_from_memory(image)?.to_rgba8();
+    let mut pixels = Vec::with_capacity(rgba.len() / 4 * 3);
+    for pixel in rgba.pixels() {
+        let alpha = pixel[3] as u32;
+        pixels.extend(
+            pixel.0[..3]
+                .iter()
+                .map(|channel| ((*channel as u32 * alpha + 255 * (255 - alpha)) / 255) as u8),
+        );
+    }
+    let mut encoder = ZlibEncoder::new(Vec::new(), Compression::default());
+    encoder.write_all(&pixels)?;
+    let filter = "/ColorSpace /DeviceRGB /Filter /FlateDecode".to_owned();
+    Ok((rgba.width(), rgba.height(), filter, encoder.finish()?))
+}
+
+/// Text string encoded as UTF-16, so that any title survives
+fn pdf_text(value: &str) -> String {
+    let hex: String = value
+        .encode_utf16()
+        .map(|unit| format!("{unit:04X}"))
+        .collect();
+    format!("<FEFF{hex}>")
+}
@@ -0,0 +1,297 @@
+use std::cmp::Reverse;
+
+use anyhow::Result;
+use atom_syndication::{ContentBuilder, EntryBuilder, FeedBuilder, LinkBuilder};
+use chrono::{DateTime, FixedOffset, Utc};
+use rss::{ChannelBuilder, GuidBuilder, ItemBuilder};
+use serde::Deserialize;
+use strum_macros::{Display, EnumString};
+
+use crate::{Fetcher, Sites, Strip, Url, escape_xml};
+
+/// Strips per site included in a feed by default
+pub const DEFAULT_FEED_LENGTH: usize = 10;
+
+const MERGED_FEED_LINK: &str = "https://github.com/newfla/daily-strip";
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Display, EnumString, Deserialize)]
+#[strum(serialize_all = "lowercase")]
+#[serde(rename_all = "lowercase")]
+pub enum FeedFormat {
+    #[default]
+    Rss,
+    Atom,
+}
+
+impl FeedFormat {
+    pub fn content_type(&self) -> &'static str {
+        match self {
+            FeedFormat::Rss => "application/rss+xml",
+            FeedFormat::Atom => "application/atom+xml",
+        }
+    }
+}
+
+/// Strip of a feed, along with the page it was found on when the site tells it
+#[derive(Debug, Clone)]
+pub struct FeedStrip {
+    pub strip: Strip,
+    pub page: Option<String>,
+}
+
+impl FeedStrip {
+    /// Page of the strip, or else its image
+    fn link(&self) -> String {
+        self.page.clone().unwrap_or_else(|| self.strip.url.clone())
+    }
+
+    /// Identifier of the entry, which must not change when the images move to another host
+    fn id(&self) -> String {
+        self.page.clone().unwrap_or_else(|| {
+            format!(
+                "urn:daily-strip:{}:{}",
+                self.strip.site.id(),
+                self.strip.idx
+            )
+        })
+    }
+}
+
+/// The `count` most recent strips of the archive, newest first
+pub async fn recent_strips<F: Fetcher + ?Sized>(
+    fetcher: &F,
+    count: usize,
+) -> Result<Vec<FeedStrip>> {
+    let mut strips = Vec::with_capacity(count);
+    if count == 0 {
+        return Ok(Vec::new());
+    }
+
+    let mut strip = fetcher.last().await?;
+    while strips.len() + 1 < count && strip.has_prev() {
+        let prev = fetcher.prev(strip.idx).await?;
+        strips.push(strip);
+        strip = prev;
+    }
+    strips.push(strip);
+    Ok(strips
+        .into_iter()
+        .map(|strip| FeedStrip {
+            page: fetcher.page_url(strip.idx),
+            strip,
+        })
+        .collect())
+}
+
+/// Feed of a single site, `strips` newest first
+pub fn site_feed(site: Sites, strips: &[FeedStrip], format: FeedFormat) -> String {
+    let link = format!("https://{}", site.homepage());
+    let items: Vec<_> = strips
+        .iter()
+        .map(|entry| (entry.strip.title.clone(), entry))
+        .collect();
+    render(&site.to_string(), &link, &link, &items, format)
+}
+
+/// Feed across several sites, each list newest first.
+///
+/// Entries are sorted by publication date, newest first. Strips without a date are interleaved
+/// site by site after the dated ones.
+pub fn merged_feed(strips: &[Vec<FeedStrip>], format: FeedFormat) -> String {
+    let sites: Vec<_> = strips
+        .iter()
+        .filter_map(|strips| strips.first().map(|entry| entry.strip.site))
+        .collect();
+    let title = sites
+        .iter()
+        .map(ToString::to_string)
+        .collect::<Vec<_>>()
+        .join(", ");
+    let id = format!(
+        "urn:daily-strip:{}",
+        sites.iter().map(Sites::id).collect::<Vec<_>>().join("+")
+    );
+
+    let longest = strips.iter().map(Vec::len).max().unwrap_or_default();
+    let mut items: Vec<_> = (0..longest)
+        .flat_map(|pos| strips.iter().filter_map(move |strips| strips.get(pos)))
+        .map(|entry| {
+            let strip = &entry.strip;
+            (format!("{} - {}", strip.site, strip.title), entry)
+        })
+        .collect();
+    items.sort_by_key(|(_, entry)| Reverse(entry.strip.published));
+    render(&title, MERGED_FEED_LINK, &id, &items, format)
+}
+
+fn render(
+    title: &str,
+    link: &str,
+    id: &str,
+    items: &[(String, &FeedStrip)],
+    format: FeedFormat,
+) -> String {
+    let description = format!("Daily Strip feed of {title}");
+    match format {
+        FeedFormat::Rss => ChannelBuilder::default()
+            .title(title)
+            .link(link)
+            .description(description)
+            .items(
+                items
+                    .iter()
+                    .map(|(title, entry)| {
+                        let guid = GuidBuilder::default()
+                            .value(entry.id())
+                            .permalink(entry.page.is_some())
+                            .build();
+                        ItemBuilder::default()
+                            .title(title.clone())
+                            .pub_date(published(&entry.strip).map(|date| date.to_rfc2822()))
+                            .link(entry.link())
+                            .guid(guid)
+                            .description(image_html(title, &entry.strip))
+                            .build()
+                    })
+                    .collect::<Vec<_>>(),
+            )
+            .build()
+            .to_string(),
+        FeedFormat::Atom => {
+            // Only strips without a date look changed on each fetch
+            let now = Utc::now().fixed_offset();
+            let updated = items
+                .iter()
+                .filter_map(|(_, entry)| published(&entry.strip))
+                .max()
+                .unwrap_or(now);
+            FeedBuilder::default()
+                .title(title)
+                .id(id)
+                .updated(updated)
+                .subtitle(Some(description.into()))
+                .link(LinkBuilder::default().href(link).build())
+                .entries(
+                    items
+                        .iter()
+                        .map(|(title, entry)| {
+                            let strip = &entry.strip;
+                            let content = ContentBuilder::default()
+                                .value(image_html(title, strip))
+                                .content_type("html".to_owned())
+                                .build();
+                            EntryBuilder::default()
+                                .title(title.as_str())
+                                .id(entry.id())
+                                .updated(published(strip).unwrap_or(now))
+                                .published(published(strip))
+                                .link(LinkBuilder::default().href(entry.link()).build())
+                                .content(content)
+                                .build()
+                        })
+                        .collect::<Vec<_>>(),
+                )
+                .build()
+                .to_string()
+        }
+    }
+}
+
+/// Midnight UTC of the day the strip was published
+fn published(strip: &Strip) -> Option<DateTime<FixedOffset>> {
+    strip
+        .published
+        .and_then(|date| date.and_hms_opt(0, 0, 0))
+        .map(|date| date.and_utc().fixed_offset())
+}
+
+/// Inline the images so that readers show the strip itself
+fn image_html(title: &str, strip: &Strip) -> String {
+    strip
+        .image_urls()
+        .into_iter()
+        .map(|url| {
+            format!(
+                r#"<img src="{}" alt="{}"/>"#,
+                escape_xml(url),
+                escape_xml(title)
+            )
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod test {
+    use atom_syndication::Feed;
+    use chrono::NaiveDate;
+    use rss::Channel;
+
+    use super::{FeedFormat, merged_feed, recent_strips, site_feed};
+    use crate::{
+        Sites,
+        fetcher::{build_fetcher_with_base_url, fixture::FixtureServer},
+    };
+
+    #[tokio::test]
+    async fn test_feeds() {
+        let routes = [("/", "index.html"), ("/*", "comic.html")];
+        let server = FixtureServer::start("xkcd", &routes).await;
+        let fetcher = build_fetcher_with_base_url(Sites::Xkcd, server.url())
+            .await
+            .unwrap();
+
+        let mut strips = recent_strips(&fetcher, 2).await.unwrap();
+        let titles: Vec<_> = strips
+            .iter()
+            .map(|entry| entry.strip.title.as_str())
+            .collect();
+        assert_eq!(titles, ["3", "2"]);
+        assert!(recent_strips(&fetcher, 0).await.unwrap().is_empty());
+
+        let rss = site_feed(Sites::Xkcd, &strips, FeedFormat::Rss);
+        let channel = Channel::read_from(rss.as_bytes()).unwrap();
+        assert_eq!(channel.title(), "xkcd");
+        assert_eq!(channel.items().len(), 2);
+        // Linked to the page of the strip, which outlives the image
+        let page = format!("{}/3", server.url());
+        assert_eq!(strips[0].page.as_deref(), Some(page.as_str()));
+        assert_eq!(channel.items()[0].link(), Some(page.as_str()));
+        let guid = channel.items()[0].guid().unwrap();
+        assert_eq!((guid.value(), guid.is_permalink()), (page.as_str(), true));
+
+        let atom = merged_feed(&[strips.clone(), strips[1..].to_vec()], FeedFormat::Atom);
+        let feed: Feed = atom.parse().unwrap();
+        let titles: Vec<_> = feed
+            .entries()
+            .iter()
+            .map(|entry| entry.title().as_str())
+            .collect();
+        assert_eq!(titles, ["xkcd - 3", "xkcd - 2", "xkcd - 2"]);
+
+        // Dated entries keep their date and are merged by it, undated ones come last
+        strips[0].strip.published = NaiveDate::from_ymd_opt(2024, 3, 1);
+        let mut other = strips[1].clone();
+        other.strip.title = "Other".to_owned();
+        other.strip.published = NaiveDate::from_ymd_opt(2024, 3, 12);
+        other.page = None;
+        let rss = site_feed(Sites::Xkcd, &strips, FeedFormat::Rss);
+        let channel = Channel::read_from(rss.as_bytes()).unwrap();
+        let dates: Vec<_> = channel.items().iter().map(|item| item.pub_date()).collect();
+        assert_eq!(dates, [Some("Fri, 1 Mar 2024 00:00:00 +0000"), None]);
+
+        let atom = merged_feed(&[strips.clone(), vec![other]], FeedFormat::Atom);
+        let feed: Feed = atom.parse().unwrap();
+        let titles: Vec<_> = feed
+            .entries()
+            .iter()
+            .map(|entry| entry.title().as_str())
+            .collect();
+        assert_eq!(titles, ["xkcd - Other", "xkcd - 3", "xkcd - 2"]);
+        assert_eq!(feed.updated().to_rfc3339(), "2024-03-12T00:00:00+00:00");
+        let first = &feed.entries()[0];
+        assert_eq!(first.updated(), &first.published().copied().unwrap());
+        // Without a page, the id doesn't depend on where the image is hosted
+        let id = format!("urn:daily-strip:xkcd:{}", strips[1].strip.idx);
+        assert_eq!(first.id(), id);
+    }
+}
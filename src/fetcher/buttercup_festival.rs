@@ -2,14 +2,14 @@ use anyhow::{Result, bail};
 use scraper::{Html, Selector};
 
 use super::FetcherImpl;
-use crate::FetcherErrors::Error404;
-use crate::{FetcherErrors, Strip, StripType, Url};
+use crate::FetcherErrors::Parse;
+use crate::{FetcherErrors, Strip, StripType};
 
 impl FetcherImpl {
     pub(super) async fn reload_buttercup_festival(&mut self) -> Result<()> {
-        let data = reqwest::get(self.site.fetch_url()).await?.text().await?;
+        let data = self.index_text(self.fetch_url()).await?;
         let frag = Html::parse_document(&data);
-        let selector = Selector::parse("a").map_err(|_| Error404)?;
+        let selector = Selector::parse("a").map_err(|err| Parse(err.to_string()))?;
         let mut data: Vec<_> = frag
             .select(&selector)
             .filter_map(|elem| {
@@ -17,13 +17,13 @@ impl FetcherImpl {
                     .parse::<usize>()
                     .map(|idx| {
                         let url = elem.value().attr("href").unwrap();
-                        Strip {
-                            title: url.split_once('.').unwrap().0.to_owned(),
-                            url: format!("{}/{}", self.site.fetch_url(), url),
+                        Strip::new(
+                            self.site,
+                            url.split_once('.').unwrap().0.to_owned(),
+                            format!("{}/{}", self.fetch_url(), url),
                             idx,
-                            strip_type: StripType::Unknown,
-                            site: self.site,
-                        }
+                            StripType::Unknown,
+                        )
                     })
                     .ok()
             })
@@ -32,7 +32,7 @@ impl FetcherImpl {
         data.sort_by_key(|s| format!("{}-{:03}", s.title.split_once('-').unwrap().0, s.idx));
         Self::reverse_strip_vec(&mut data);
         match data.len() {
-            0 => bail!(FetcherErrors::Error404),
+            0 => bail!(FetcherErrors::EmptyArchive),
             _ => {
                 self.posts = Some(data);
                 Ok(())
@@ -41,16 +41,17 @@ impl FetcherImpl {
     }
 
     pub(super) async fn parse_buttercup_festival_content(&self, content: &Strip) -> Result<Strip> {
-        let data = reqwest::get(&content.url).await?.text().await?;
+        let data = self.http.text(&content.url).await?;
         let url = Self::parse_first_occurrence_blocking(&data, "center img", "src")
-            .ok_or(FetcherErrors::Error404)?;
+            .ok_or_else(|| FetcherErrors::layout_changed("center img"))?;
 
-        Ok(Strip {
-            title: content.title.clone(),
-            url: format!("{}/{}", self.site.fetch_url(), url),
-            idx: content.idx,
-            strip_type: content.strip_type,
-            site: content.site,
-        })
+        Ok(Strip::new(
+            content.site,
+            content.title.clone(),
+            format!("{}/{}", self.fetch_url(), url),
+            content.idx,
+            content.strip_type,
+        )
+        .with_published(content.published))
     }
 }
@@ -2,32 +2,26 @@ use anyhow::{Result, bail};
 use scraper::{Html, Selector};
 
 use super::FetcherImpl;
-use crate::FetcherErrors::Error404;
-use crate::{FetcherErrors, Strip, StripType, Url};
+use crate::FetcherErrors::Parse;
+use crate::{FetcherErrors, Strip, StripType};
 
 impl FetcherImpl {
     pub(super) async fn reload_cat_and_girl(&mut self) -> Result<()> {
-        let data = reqwest::get(self.site.fetch_url()).await?.text().await?;
+        let data = self.index_text(self.fetch_url()).await?;
         let frag = Html::parse_document(&data);
-        let selector = Selector::parse("a.sya_postlink").map_err(|_| Error404)?;
+        let selector = Selector::parse("a.sya_postlink").map_err(|err| Parse(err.to_string()))?;
         let data: Vec<_> = frag
             .select(&selector)
             .enumerate()
             .map(|(idx, elem)| {
                 let title = elem.inner_html();
                 let url = elem.attr("href").unwrap().to_owned();
-                Strip {
-                    title,
-                    url,
-                    idx,
-                    strip_type: StripType::Unknown,
-                    site: self.site,
-                }
+                Strip::new(self.site, title, url, idx, StripType::Unknown)
             })
             .collect();
 
         match data.len() {
-            0 => bail!(FetcherErrors::Error404),
+            0 => bail!(FetcherErrors::EmptyArchive),
             _ => {
                 self.posts = Some(data);
                 Ok(())
@@ -36,16 +30,17 @@ impl FetcherImpl {
     }
 
     pub(super) async fn parse_cat_and_girl_content(&self, content: &Strip) -> Result<Strip> {
-        let data = reqwest::get(&content.url).await?.text().await?;
+        let data = self.http.text(&content.url).await?;
         let url = Self::parse_first_occurrence_blocking(&data, "div.comic--container img", "src")
-            .ok_or(FetcherErrors::Error404)?;
+            .ok_or_else(|| FetcherErrors::layout_changed("div.comic--container img"))?;
 
-        Ok(Strip {
-            title: content.title.clone(),
+        Ok(Strip::new(
+            content.site,
+            content.title.clone(),
             url,
-            idx: content.idx,
-            strip_type: content.strip_type,
-            site: content.site,
-        })
+            content.idx,
+            content.strip_type,
+        )
+        .with_published(content.published))
     }
 }
@@ -2,15 +2,17 @@ use anyhow::{Result, bail};
 use scraper::{Html, Selector};
 
 use super::FetcherImpl;
-use crate::FetcherErrors::Error404;
-use crate::{FetcherErrors, Strip, StripType, Url};
+use crate::FetcherErrors::Parse;
+use crate::{FetcherErrors, Strip, StripType};
 
 impl FetcherImpl {
     pub(super) async fn reload_cornet_comics(&mut self) -> Result<()> {
-        let data = reqwest::get(self.site.fetch_url()).await?.text().await?;
+        let data = self.index_text(self.fetch_url()).await?;
         let frag = Html::parse_document(&data);
-        let selector_name = Selector::parse("span a.post-link").map_err(|_| Error404)?;
-        let selector_url = Selector::parse("a.post-link img").map_err(|_| Error404)?;
+        let selector_name =
+            Selector::parse("span a.post-link").map_err(|err| Parse(err.to_string()))?;
+        let selector_url =
+            Selector::parse("a.post-link img").map_err(|err| Parse(err.to_string()))?;
 
         let data: Vec<_> = frag
             .select(&selector_name)
@@ -18,21 +20,23 @@ impl FetcherImpl {
             .map(|(a_title, img_url)| (a_title.inner_html(), img_url.value().attr("src")))
             .filter(|(title, thumb_url)| !title.is_empty() && thumb_url.is_some())
             .enumerate()
-            .map(|(idx, (name, thumb_url))| Strip {
-                title: name.trim().to_string(),
-                url: self.site.fetch_url().to_owned()
-                    + &thumb_url
-                        .unwrap()
-                        .to_string()
-                        .replace("thumbs/", "")
-                        .replace("_thumbnail", ""),
-                idx,
-                strip_type: StripType::Unknown,
-                site: self.site,
+            .map(|(idx, (name, thumb_url))| {
+                Strip::new(
+                    self.site,
+                    name.trim().to_string(),
+                    self.fetch_url()
+                        + &thumb_url
+                            .unwrap()
+                            .to_string()
+                            .replace("thumbs/", "")
+                            .replace("_thumbnail", ""),
+                    idx,
+                    StripType::Unknown,
+                )
             })
             .collect();
         match data.len() {
-            0 => bail!(FetcherErrors::Error404),
+            0 => bail!(FetcherErrors::EmptyArchive),
             _ => {
                 self.posts = Some(data);
                 Ok(())
@@ -1,24 +1,26 @@
 use anyhow::{Result, bail};
 
-use crate::{FetcherErrors, Strip, StripType, Url};
+use crate::{FetcherErrors, Strip, StripType};
 
 use super::FetcherImpl;
 
 impl FetcherImpl {
     pub(super) async fn reload_diesel_sweeties_1_0(&mut self) -> Result<()> {
         let mut data: Vec<_> = (1..=4000)
-            .map(|idx| Strip {
-                title: idx.to_string(),
-                url: format!("{}/{idx}", self.site.fetch_url()),
-                idx: idx - 1,
-                strip_type: StripType::Unknown,
-                site: self.site,
+            .map(|idx| {
+                Strip::new(
+                    self.site,
+                    idx.to_string(),
+                    format!("{}/{idx}", self.fetch_url()),
+                    idx - 1,
+                    StripType::Unknown,
+                )
             })
             .collect();
 
         Self::reverse_strip_vec(&mut data);
         match data.len() {
-            0 => bail!(FetcherErrors::Error404),
+            0 => bail!(FetcherErrors::EmptyArchive),
             _ => {
                 self.posts = Some(data);
                 Ok(())
@@ -27,21 +29,15 @@ impl FetcherImpl {
     }
 
     pub(super) async fn parse_diesel_sweeties_1_0_content(&self, content: &Strip) -> Result<Strip> {
-        //Something is wrong with site certificate
-        let data = reqwest::Client::builder()
-            .danger_accept_invalid_certs(true)
-            .build()?
-            .get(&content.url)
-            .send()
-            .await?
-            .text()
-            .await?;
+        let data = self.http.text(&content.url).await?;
         let url = Self::parse_first_occurrence_blocking(
             &data,
             "center ~ table ~ div > table > tbody > tr td div > img",
             "src",
         )
-        .ok_or(FetcherErrors::Error404)?;
+        .ok_or_else(|| {
+            FetcherErrors::layout_changed("center ~ table ~ div > table > tbody > tr td div > img")
+        })?;
 
         let title = Self::parse_first_occurrence_blocking(
             &data,
@@ -50,13 +46,18 @@ impl FetcherImpl {
         )
         .unwrap_or_else(|| content.title.clone());
 
-        Ok(Strip {
+        Ok(Strip::new(
+            content.site,
             title,
             // Switch to http to avoid image not loading due to wrong certificates
-            url: format!("http://{}{}", self.site.homepage(), url),
-            idx: content.idx,
-            strip_type: content.strip_type,
-            site: content.site,
-        })
+            format!(
+                "{}{}",
+                self.homepage_url().replacen("https://", "http://", 1),
+                url
+            ),
+            content.idx,
+            content.strip_type,
+        )
+        .with_published(content.published))
     }
 }
@@ -1,48 +1,40 @@
 use anyhow::{Result, bail};
-use rss::Channel;
 
-use crate::{FetcherErrors, Strip, StripType, Url};
+use crate::{FetcherErrors, Strip, StripType};
 
 use super::FetcherImpl;
 
 impl FetcherImpl {
     pub(super) async fn reload_diesel_sweeties_3_0(&mut self) -> Result<()> {
-        //Something is wrong with site certificate
-        let data = reqwest::Client::builder()
-            .danger_accept_invalid_certs(true)
-            .build()?
-            .get(self.site.fetch_url())
-            .send()
-            .await?
-            .bytes()
-            .await?;
+        let data = self.index_bytes(self.fetch_url()).await?;
 
-        let data: Vec<_> = Channel::read_from(&data[..])?
+        let data: Vec<_> = Self::read_feed(&data)?
             .items
             .into_iter()
-            .map(|item| (item.title, item.description))
-            .filter(|(title, description)| {
+            .map(|item| (Self::rss_date(&item), item.title, item.description))
+            .filter(|(_, title, description)| {
                 title.as_ref().is_some_and(|title| !title.is_empty())
                     && description
                         .as_ref()
                         .is_some_and(|description| !description.is_empty())
             })
             .enumerate()
-            .map(|(idx, (title, description))| {
+            .map(|(idx, (published, title, description))| {
                 let url =
                     Self::parse_first_occurrence_blocking(&description.unwrap(), "img", "src");
-                Strip {
-                    title: title.unwrap(),
-                    url: url.unwrap(),
+                Strip::new(
+                    self.site,
+                    title.unwrap(),
+                    url.unwrap(),
                     idx,
-                    strip_type: StripType::Unknown,
-                    site: self.site,
-                }
+                    StripType::Unknown,
+                )
+                .with_published(published)
             })
             .collect();
 
         match data.len() {
-            0 => bail!(FetcherErrors::Error404),
+            0 => bail!(FetcherErrors::EmptyArchive),
             _ => {
                 self.posts = Some(data);
                 Ok(())
@@ -2,17 +2,14 @@ use anyhow::{Result, bail};
 use scraper::{Element, Html, Selector};
 
 use super::FetcherImpl;
-use crate::FetcherErrors::Error404;
-use crate::{FetcherErrors, Strip, StripType, Url};
+use crate::FetcherErrors::Parse;
+use crate::{FetcherErrors, Strip, StripType};
 
 impl FetcherImpl {
     pub(super) async fn reload_dinosaur_comics(&mut self) -> Result<()> {
-        let data = reqwest::get(self.site.fetch_url().to_owned() + "/archive.php")
-            .await?
-            .text()
-            .await?;
+        let data = self.index_text(self.fetch_url() + "/archive.php").await?;
         let frag = Html::parse_document(&data);
-        let selector = Selector::parse("ul.archive li a").map_err(|_| Error404)?;
+        let selector = Selector::parse("ul.archive li a").map_err(|err| Parse(err.to_string()))?;
         let data: Vec<_> = frag
             .select(&selector)
             .map(|element| {
@@ -29,17 +26,19 @@ impl FetcherImpl {
             })
             .filter(|(title, url)| !title.is_empty() && url.is_some())
             .enumerate()
-            .map(|(idx, (title, url))| Strip {
-                title,
-                url: url.unwrap().to_string(),
-                idx,
-                strip_type: StripType::Unknown,
-                site: self.site,
+            .map(|(idx, (title, url))| {
+                Strip::new(
+                    self.site,
+                    title,
+                    url.unwrap().to_string(),
+                    idx,
+                    StripType::Unknown,
+                )
             })
             .collect();
 
         match data.len() {
-            0 => bail!(FetcherErrors::Error404),
+            0 => bail!(FetcherErrors::EmptyArchive),
             _ => {
                 self.posts = Some(data);
                 Ok(())
@@ -48,16 +47,20 @@ impl FetcherImpl {
     }
 
     pub(super) async fn parse_dinosaur_comics_content(&self, content: &Strip) -> Result<Strip> {
-        let data = reqwest::get(&content.url).await?.text().await?;
+        let data = self.http.text(&content.url).await?;
         let url = Self::parse_first_occurrence_blocking(&data, "img.comic", "src")
-            .ok_or(FetcherErrors::Error404)?;
+            .ok_or_else(|| FetcherErrors::layout_changed("img.comic"))?;
+        let (alt_text, hover_text) = Self::parse_image_texts_blocking(&data, "img.comic");
 
-        Ok(Strip {
-            title: content.title.clone(),
-            url: self.site.fetch_url().to_owned() + "/" + &url,
-            idx: content.idx,
-            strip_type: content.strip_type,
-            site: content.site,
-        })
+        Ok(Strip::new(
+            content.site,
+            content.title.clone(),
+            self.fetch_url() + "/" + &url,
+            content.idx,
+            content.strip_type,
+        )
+        .with_alt_text(alt_text)
+        .with_hover_text(hover_text)
+        .with_published(content.published))
     }
 }
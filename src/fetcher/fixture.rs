@@ -0,0 +1,177 @@
+use std::{
+    path::PathBuf,
+    sync::{
+        Arc, Mutex,
+        atomic::{AtomicUsize, Ordering},
+    },
+};
+
+use tokio::{
+    io::{AsyncReadExt, AsyncWriteExt},
+    net::TcpListener,
+    spawn,
+    task::JoinHandle,
+};
+
+/// Placeholder replaced by the server address inside fixture files
+const BASE_PLACEHOLDER: &str = "{{base}}";
+
+/// Response recorded for a route
+#[derive(Clone)]
+struct Fixture {
+    /// Code and reason of the status line
+    status: String,
+    /// Extra header lines, each one ending with `\r\n`
+    headers: String,
+    body: Vec<u8>,
+}
+
+/// Minimal HTTP server replaying recorded pages from `tests/fixtures/<dir>`.
+///
+/// Routes map a request path (query string excluded) to a fixture file, routes with a query
+/// string match it as well. A route ending with `*` matches every path starting with it.
+///
+/// Responses carry an `ETag`, requests sending it back in `If-None-Match` get a 304. The lines of
+/// `<file>.headers`, when present, are added to the responses serving `file`, a `Status` line
+/// replacing the `200 OK`.
+pub(crate) struct FixtureServer {
+    base_url: String,
+    requests: Arc<Mutex<Vec<String>>>,
+    not_modified: Arc<AtomicUsize>,
+    task: JoinHandle<()>,
+}
+
+impl FixtureServer {
+    pub(crate) async fn start(dir: &str, routes: &[(&str, &str)]) -> Self {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let base_url = format!("http://{}", listener.local_addr().unwrap());
+        let root = PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+            .join("tests")
+            .join("fixtures")
+            .join(dir);
+        let routes: Vec<_> = routes
+            .iter()
+            .map(|(path, file)| {
+                let body = std::fs::read(root.join(file))
+                    .unwrap_or_else(|_| panic!("missing fixture {dir}/{file}"));
+                // Images are served untouched
+                let body = match String::from_utf8(body) {
+                    Ok(text) => text.replace(BASE_PLACEHOLDER, &base_url).into_bytes(),
+                    Err(err) => err.into_bytes(),
+                };
+                let mut status = "200 OK".to_owned();
+                let mut headers = String::new();
+                let extra = std::fs::read_to_string(root.join(format!("{file}.headers")));
+                for line in extra.iter().flat_map(|extra| extra.lines()) {
+                    match line.strip_prefix("Status:") {
+                        Some(line) => status = line.trim().to_owned(),
+                        None => headers.push_str(&format!("{line}\r\n")),
+                    }
+                }
+                (
+                    path.to_string(),
+                    Fixture {
+                        status,
+                        headers,
+                        body,
+                    },
+                )
+            })
+            .collect();
+
+        let requests = Arc::new(Mutex::new(Vec::new()));
+        let requests_log = requests.clone();
+        let not_modified = Arc::new(AtomicUsize::new(0));
+        let not_modified_count = not_modified.clone();
+        let task = spawn(async move {
+            while let Ok((mut stream, _)) = listener.accept().await {
+                let routes = routes.clone();
+                let requests = requests_log.clone();
+                let not_modified = not_modified_count.clone();
+                spawn(async move {
+                    let mut buf = Vec::new();
+                    let mut chunk = [0; 1024];
+                    while !buf.windows(4).any(|w| w == b"\r\n\r\n") {
+                        match stream.read(&mut chunk).await {
+                            Ok(0) | Err(_) => return,
+                            Ok(n) => buf.extend_from_slice(&chunk[..n]),
+                        }
+                    }
+                    let request = String::from_utf8_lossy(&buf);
+                    let target = request.split_whitespace().nth(1).unwrap_or("/");
+                    let path = target.split('?').next().unwrap_or("/");
+                    requests.lock().unwrap().push(path.to_owned());
+
+                    let fixture = routes
+                        .iter()
+                        .enumerate()
+                        .find_map(|(pos, (route, fixture))| {
+                            let path = if route.contains('?') { target } else { path };
+                            let matched = match route.strip_suffix('*') {
+                                Some(prefix) => path.starts_with(prefix),
+                                None => path == route,
+                            };
+                            matched.then(|| (format!("\"{pos}-{}\"", fixture.body.len()), fixture))
+                        });
+                    let cached = |etag: &str| {
+                        request.lines().any(|line| {
+                            line.split_once(':').is_some_and(|(name, value)| {
+                                name.eq_ignore_ascii_case("if-none-match") && value.trim() == etag
+                            })
+                        })
+                    };
+                    let response = match fixture {
+                        Some((etag, _)) if cached(&etag) => {
+                            not_modified.fetch_add(1, Ordering::SeqCst);
+                            format!(
+                                "HTTP/1.1 304 Not Modified\r\nETag: {etag}\r\nConnection: close\r\n\r\n"
+                            )
+                            .into_bytes()
+                        }
+                        Some((etag, fixture)) => [
+                            format!(
+                                "HTTP/1.1 {}\r\nContent-Length: {}\r\nETag: {etag}\r\n{}Connection: close\r\n\r\n",
+                                fixture.status,
+                                fixture.body.len(),
+                                fixture.headers
+                            )
+                            .as_bytes(),
+                            &fixture.body,
+                        ]
+                        .concat(),
+                        None => b"HTTP/1.1 404 Not Found\r\nContent-Length: 0\r\nConnection: close\r\n\r\n"
+                            .to_vec(),
+                    };
+                    let _ = stream.write_all(&response).await;
+                });
+            }
+        });
+
+        Self {
+            base_url,
+            requests,
+            not_modified,
+            task,
+        }
+    }
+
+    pub(crate) fn url(&self) -> &str {
+        &self.base_url
+    }
+
+    /// Paths requested so far, in arrival order
+    pub(crate) fn requests(&self) -> Vec<String> {
+        self.requests.lock().unwrap().clone()
+    }
+
+    /// Requests answered with a 304 so far
+    pub(crate) fn not_modified(&self) -> usize {
+        self.not_modified.load(Ordering::SeqCst)
+    }
+}
+
+impl Drop for FixtureServer {
+    fn drop(&mut self) {
+        self.task.abort();
+    }
+}
@@ -0,0 +1,97 @@
+use anyhow::{Result, bail};
+use reqwest::Url;
+use scraper::{Html, Selector};
+
+use super::FetcherImpl;
+use crate::FetcherErrors::Parse;
+use crate::custom::{CustomSite, Order};
+use crate::{FetcherErrors, Strip, StripType};
+
+impl FetcherImpl {
+    pub(super) async fn reload_generic(&mut self, site: CustomSite) -> Result<()> {
+        let definition = site.definition().ok_or(FetcherErrors::Unavailable)?;
+        let index_url = self.fetch_url();
+        let data = self.index_text(index_url.clone()).await?;
+        let frag = Html::parse_document(&data);
+        let selector =
+            Selector::parse(&definition.list_selector).map_err(|err| Parse(err.to_string()))?;
+        let mut data: Vec<_> = frag
+            .select(&selector)
+            .filter_map(|elem| {
+                let url = elem.value().attr(&definition.link_attr)?;
+                let title = match definition.title_attr.as_deref() {
+                    Some(attr) => elem.value().attr(attr)?.trim().to_owned(),
+                    None => elem.text().collect::<String>().trim().to_owned(),
+                };
+                Some(Strip::new(
+                    self.site,
+                    title,
+                    self.generic_absolute_url(site, &index_url, url),
+                    0,
+                    StripType::Unknown,
+                ))
+            })
+            .collect();
+
+        if definition.order == Order::OldestFirst {
+            data.reverse();
+        }
+        match data.len() {
+            0 => bail!(FetcherErrors::EmptyArchive),
+            _ => {
+                self.posts = Some(data);
+                Ok(())
+            }
+        }
+    }
+
+    pub(super) async fn parse_generic_content(
+        &self,
+        site: CustomSite,
+        content: &Strip,
+    ) -> Result<Strip> {
+        let definition = site.definition().ok_or(FetcherErrors::Unavailable)?;
+        let data = self.http.text(&content.url).await?;
+        let mut urls = Self::parse_all_occurrences_blocking(
+            &data,
+            &definition.image_selector,
+            &definition.image_attr,
+        )
+        .into_iter()
+        .map(|url| self.generic_absolute_url(site, &content.url, &url));
+        let url = urls
+            .next()
+            .ok_or_else(|| FetcherErrors::layout_changed(&definition.image_selector))?;
+        let (alt_text, hover_text) =
+            Self::parse_image_texts_blocking(&data, &definition.image_selector);
+
+        Ok(Strip::new(
+            content.site,
+            content.title.clone(),
+            url,
+            content.idx,
+            content.strip_type,
+        )
+        .with_alt_text(alt_text)
+        .with_hover_text(hover_text)
+        .with_published(content.published)
+        .with_extra_urls(urls.collect()))
+    }
+
+    /// `url` found in the page at `page_url`, made absolute with the prefix of the definition or
+    /// against the page itself
+    fn generic_absolute_url(&self, site: CustomSite, page_url: &str, url: &str) -> String {
+        if url.starts_with("http://") || url.starts_with("https://") {
+            return url.to_owned();
+        }
+        match site
+            .definition()
+            .and_then(|definition| definition.url_prefix.as_deref())
+        {
+            Some(prefix) => format!("{}{url}", self.rebase(prefix)),
+            None => Url::parse(page_url)
+                .and_then(|page| page.join(url))
+                .map_or_else(|_| url.to_owned(), String::from),
+        }
+    }
+}
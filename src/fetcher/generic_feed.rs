@@ -0,0 +1,146 @@
+use anyhow::{Result, bail};
+use reqwest::Url;
+use rss::{Item, extension::Extension};
+
+use super::{FetcherImpl, absolute_url};
+use crate::custom::{FeedSubscription, free_info};
+use crate::http::HttpClient;
+use crate::{FetcherErrors, Strip, StripType};
+
+impl FetcherImpl {
+    /// Posts keep the link of their item as `url` and the images found in the feed as
+    /// `extra_urls`, until they are resolved
+    pub(super) async fn reload_generic_feed(&mut self) -> Result<()> {
+        let data = self.index_bytes(self.fetch_url()).await?;
+        let data: Vec<_> = Self::read_feed(&data)?
+            .items
+            .iter()
+            .filter_map(|item| {
+                let images = feed_item_images(item);
+                let url = item
+                    .link()
+                    .filter(|link| !link.is_empty())
+                    .or(images.first().map(String::as_str))?
+                    .to_owned();
+                let title = item
+                    .title()
+                    .map(str::trim)
+                    .filter(|title| !title.is_empty())
+                    .unwrap_or(&url)
+                    .to_owned();
+                Some(
+                    Strip::new(self.site, title, url, 0, StripType::Unknown)
+                        .with_published(Self::rss_date(item))
+                        .with_extra_urls(images),
+                )
+            })
+            .collect();
+
+        match data.len() {
+            0 => bail!(FetcherErrors::EmptyArchive),
+            _ => {
+                self.posts = Some(data);
+                Ok(())
+            }
+        }
+    }
+
+    /// Images of the feed item, or else the `og:image` of the page it links to
+    pub(super) async fn parse_generic_feed_content(&self, content: &Strip) -> Result<Strip> {
+        let mut urls = content.extra_urls.clone().into_iter();
+        let url = match urls.next() {
+            Some(url) => url,
+            None => {
+                let data = self.http.text(&content.url).await?;
+                let image = Self::parse_first_occurrence_blocking(
+                    &data,
+                    r#"meta[property="og:image"]"#,
+                    "content",
+                )
+                .ok_or_else(|| FetcherErrors::layout_changed("og:image"))?;
+                absolute_url(&content.url, &image)
+            }
+        };
+
+        Ok(Strip::new(
+            content.site,
+            content.title.clone(),
+            url,
+            content.idx,
+            content.strip_type,
+        )
+        .with_published(content.published)
+        .with_extra_urls(urls.collect()))
+    }
+}
+
+/// Subscription to the feed at `url`, named after its title
+pub(super) async fn discover_feed(http: &HttpClient, url: &str) -> Result<FeedSubscription> {
+    let data = http.bytes(url).await?;
+    let channel = FetcherImpl::read_feed(&data)?;
+    if channel.items.is_empty() {
+        bail!(FetcherErrors::EmptyArchive);
+    }
+    let homepage = match channel.link.trim() {
+        "" => Url::parse(url)?.join("/")?.to_string(),
+        link => link.to_owned(),
+    };
+    Ok(FeedSubscription {
+        info: free_info(&channel.title, homepage),
+        url: url.to_owned(),
+    })
+}
+
+/// Images of an RSS item, from the first of its enclosures, `media:content` elements or the
+/// `<img>` of its HTML that has some
+fn feed_item_images(item: &Item) -> Vec<String> {
+    let base = item.link().unwrap_or_default();
+    let enclosures: Vec<_> = item
+        .enclosure()
+        .filter(|enclosure| enclosure.mime_type().starts_with("image/"))
+        .map(|enclosure| enclosure.url().to_owned())
+        .into_iter()
+        .collect();
+    if !enclosures.is_empty() {
+        return enclosures;
+    }
+
+    let media = item.extensions().get("media");
+    let contents = media.and_then(|media| media.get("content"));
+    let grouped = media
+        .and_then(|media| media.get("group"))
+        .into_iter()
+        .flatten()
+        .filter_map(|group| group.children().get("content"))
+        .flatten();
+    let media: Vec<_> = contents
+        .into_iter()
+        .flatten()
+        .chain(grouped)
+        .filter(|content| is_media_image(content))
+        .filter_map(|content| content.attrs().get("url").cloned())
+        .collect();
+    if !media.is_empty() {
+        return media;
+    }
+
+    [item.content(), item.description()]
+        .into_iter()
+        .flatten()
+        .map(|html| FetcherImpl::parse_all_occurrences_blocking(html, "img", "src"))
+        .find(|images| !images.is_empty())
+        .unwrap_or_default()
+        .into_iter()
+        .map(|image| absolute_url(base, &image))
+        .collect()
+}
+
+/// `media:content` elements tell images apart with `medium` or `type`, when they do at all
+fn is_media_image(content: &Extension) -> bool {
+    let attrs = content.attrs();
+    match (attrs.get("medium"), attrs.get("type")) {
+        (Some(medium), _) => medium == "image",
+        (None, Some(mime_type)) => mime_type.starts_with("image/"),
+        (None, None) => true,
+    }
+}
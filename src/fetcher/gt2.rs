@@ -1,34 +1,36 @@
 use anyhow::{Result, bail};
-use rss::Channel;
 
-use crate::{FetcherErrors, Strip, StripType, Url};
+use crate::{FetcherErrors, Strip, StripType};
 
 use super::FetcherImpl;
 
 impl FetcherImpl {
     pub(super) async fn reload_gt2(&mut self) -> Result<()> {
-        let data = reqwest::get(self.site.fetch_url()).await?.bytes().await?;
-        let data: Vec<_> = Channel::read_from(&data[..])?
+        let data = self.index_bytes(self.fetch_url()).await?;
+        let data: Vec<_> = Self::read_feed(&data)?
             .items
             .into_iter()
-            .map(|item| (item.title, item.content))
-            .filter(|(title, content)| {
+            .map(|item| (Self::rss_date(&item), item.title, item.content))
+            .filter(|(_, title, content)| {
                 title.as_ref().is_some_and(|title| !title.is_empty())
                     && content
                         .as_ref()
                         .is_some_and(|description| !description.is_empty())
             })
             .enumerate()
-            .map(|(idx, (title, content))| Strip {
-                title: title.unwrap(),
-                url: content.unwrap(),
-                idx,
-                strip_type: StripType::Unknown,
-                site: self.site,
+            .map(|(idx, (published, title, content))| {
+                Strip::new(
+                    self.site,
+                    title.unwrap(),
+                    content.unwrap(),
+                    idx,
+                    StripType::Unknown,
+                )
+                .with_published(published)
             })
             .collect();
         match data.len() {
-            0 => bail!(FetcherErrors::Error404),
+            0 => bail!(FetcherErrors::EmptyArchive),
             _ => {
                 self.posts = Some(data);
                 Ok(())
@@ -38,14 +40,15 @@ impl FetcherImpl {
 
     pub(super) async fn parse_gt2_content(&self, content: &Strip) -> Result<Strip> {
         let url = Self::parse_first_occurrence_blocking(&content.url, "img", "src")
-            .ok_or(FetcherErrors::Error404)?
+            .ok_or_else(|| FetcherErrors::layout_changed("img"))?
             .replace("..", &content.title);
-        Ok(Strip {
-            title: content.title.clone(),
+        Ok(Strip::new(
+            content.site,
+            content.title.clone(),
             url,
-            idx: content.idx,
-            strip_type: content.strip_type,
-            site: content.site,
-        })
+            content.idx,
+            content.strip_type,
+        )
+        .with_published(content.published))
     }
 }
@@ -2,32 +2,34 @@ use anyhow::{Result, bail};
 use scraper::{Html, Selector};
 
 use super::FetcherImpl;
-use crate::FetcherErrors::Error404;
-use crate::{FetcherErrors, Strip, StripType, Url};
+use crate::FetcherErrors::Parse;
+use crate::{FetcherErrors, Strip, StripType};
 
 impl FetcherImpl {
     pub(super) async fn reload_gunnerkrigg_court(&mut self) -> Result<()> {
-        let data = reqwest::get(self.site.fetch_url()).await?.text().await?;
+        let data = self.index_text(self.fetch_url()).await?;
         let frag = Html::parse_document(&data);
-        let selector = Selector::parse("option").map_err(|_| Error404)?;
+        let selector = Selector::parse("option").map_err(|err| Parse(err.to_string()))?;
         let limit = frag
             .select(&selector)
             .next_back()
             .map(|elem| elem.attr("value").unwrap().parse::<usize>().unwrap())
             .unwrap();
         let mut data: Vec<_> = (1..=limit)
-            .map(|idx| Strip {
-                title: idx.to_string(),
-                url: format!("https://{}/comics/{:08}.jpg", self.site.homepage(), idx),
-                idx: idx - 1,
-                strip_type: StripType::Unknown,
-                site: self.site,
+            .map(|idx| {
+                Strip::new(
+                    self.site,
+                    idx.to_string(),
+                    format!("{}/comics/{:08}.jpg", self.homepage_url(), idx),
+                    idx - 1,
+                    StripType::Unknown,
+                )
             })
             .collect();
 
         Self::reverse_strip_vec(&mut data);
         match data.len() {
-            0 => bail!(FetcherErrors::Error404),
+            0 => bail!(FetcherErrors::EmptyArchive),
             _ => {
                 self.posts = Some(data);
                 Ok(())
@@ -36,12 +38,13 @@ impl FetcherImpl {
     }
 
     pub(super) async fn parse_gunnerkrigg_court_content(&self, content: &Strip) -> Result<Strip> {
-        Ok(Strip {
-            title: content.title.clone(),
-            url: content.url.clone(),
-            idx: content.idx,
-            strip_type: content.strip_type,
-            site: content.site,
-        })
+        Ok(Strip::new(
+            content.site,
+            content.title.clone(),
+            content.url.clone(),
+            content.idx,
+            content.strip_type,
+        )
+        .with_published(content.published))
     }
 }
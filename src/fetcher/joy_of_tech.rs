@@ -2,30 +2,24 @@ use anyhow::{Result, bail};
 use scraper::{Html, Selector};
 
 use super::FetcherImpl;
-use crate::FetcherErrors::Error404;
-use crate::{FetcherErrors, Strip, StripType, Url};
+use crate::FetcherErrors::Parse;
+use crate::{FetcherErrors, Strip, StripType};
 
 impl FetcherImpl {
     pub(super) async fn reload_joy_of_tech(&mut self) -> Result<()> {
-        let data = reqwest::get(self.site.fetch_url()).await?.text().await?;
+        let data = self.index_text(self.fetch_url()).await?;
         let frag = Html::parse_document(&data);
-        let selector = Selector::parse("h3 a").map_err(|_| Error404)?;
+        let selector = Selector::parse("h3 a").map_err(|err| Parse(err.to_string()))?;
         let data: Vec<_> = frag
             .select(&selector)
             .enumerate()
             .map(|(idx, elem)| {
                 let url = elem.value().attr("href").unwrap().to_owned();
-                Strip {
-                    title: format!("https://{}", self.site.homepage()),
-                    url,
-                    idx,
-                    strip_type: StripType::Unknown,
-                    site: self.site,
-                }
+                Strip::new(self.site, self.homepage_url(), url, idx, StripType::Unknown)
             })
             .collect();
         match data.len() {
-            0 => bail!(FetcherErrors::Error404),
+            0 => bail!(FetcherErrors::EmptyArchive),
             _ => {
                 self.posts = Some(data);
                 Ok(())
@@ -34,19 +28,16 @@ impl FetcherImpl {
     }
 
     pub(super) async fn parse_joy_of_tech_content(&self, content: &Strip) -> Result<Strip> {
-        let data = reqwest::get(&content.url).await?.text().await?;
+        let data = self.http.text(&content.url).await?;
         let url = Self::parse_first_occurrence_blocking(&data, "p.Maintext img", "src")
-            .ok_or(FetcherErrors::Error404)?
+            .ok_or_else(|| FetcherErrors::layout_changed("p.Maintext img"))?
             .replace("..", &content.title);
 
         let title = Self::parse_first_occurrence_blocking(&data, "p.Maintext img", "alt")
-            .ok_or(FetcherErrors::Error404)?;
-        Ok(Strip {
-            title,
-            url,
-            idx: content.idx,
-            strip_type: content.strip_type,
-            site: content.site,
-        })
+            .ok_or_else(|| FetcherErrors::layout_changed("p.Maintext img"))?;
+        Ok(
+            Strip::new(content.site, title, url, content.idx, content.strip_type)
+                .with_published(content.published),
+        )
     }
 }
@@ -2,31 +2,32 @@ use anyhow::{Result, bail};
 use scraper::{Html, Selector};
 
 use super::FetcherImpl;
-use crate::FetcherErrors::Error404;
-use crate::{FetcherErrors, Strip, StripType, Url};
+use crate::FetcherErrors::Parse;
+use crate::{FetcherErrors, Strip, StripType};
 
 impl FetcherImpl {
     pub(super) async fn reload_js_power_hour(&mut self) -> Result<()> {
-        let data = reqwest::get(self.site.fetch_url()).await?.text().await?;
+        let data = self.index_text(self.fetch_url()).await?;
         let frag = Html::parse_document(&data);
-        let selector = Selector::parse("div.archive-comic a").map_err(|_| Error404)?;
+        let selector =
+            Selector::parse("div.archive-comic a").map_err(|err| Parse(err.to_string()))?;
         let data: Vec<_> = frag
             .select(&selector)
             .enumerate()
             .map(|(idx, elem)| {
                 let url = elem.value().attr("href").unwrap().to_owned();
                 let title = url.split_once("/comics/").unwrap().1.to_owned();
-                Strip {
+                Strip::new(
+                    self.site,
                     title,
-                    url: format!("https://{}{}", self.site.homepage(), url),
+                    format!("{}{}", self.homepage_url(), url),
                     idx,
-                    strip_type: StripType::Unknown,
-                    site: self.site,
-                }
+                    StripType::Unknown,
+                )
             })
             .collect();
         match data.len() {
-            0 => bail!(FetcherErrors::Error404),
+            0 => bail!(FetcherErrors::EmptyArchive),
             _ => {
                 self.posts = Some(data);
                 Ok(())
@@ -35,16 +36,17 @@ impl FetcherImpl {
     }
 
     pub(super) async fn parse_js_power_hour_content(&self, content: &Strip) -> Result<Strip> {
-        let data = reqwest::get(&content.url).await?.text().await?;
+        let data = self.http.text(&content.url).await?;
         let url = Self::parse_first_occurrence_blocking(&data, "#comic-img", "src")
-            .ok_or(FetcherErrors::Error404)?;
+            .ok_or_else(|| FetcherErrors::layout_changed("#comic-img"))?;
 
-        Ok(Strip {
-            title: content.title.clone(),
-            url: format!("https:{url}"),
-            idx: content.idx,
-            strip_type: content.strip_type,
-            site: content.site,
-        })
+        Ok(Strip::new(
+            content.site,
+            content.title.clone(),
+            format!("https:{url}"),
+            content.idx,
+            content.strip_type,
+        )
+        .with_published(content.published))
     }
 }
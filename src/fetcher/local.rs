@@ -0,0 +1,139 @@
+use std::path::{Path, PathBuf};
+
+use anyhow::{Result, bail};
+use async_trait::async_trait;
+use chrono::NaiveDate;
+use rand::{RngExt, rng};
+
+use super::nearest_by_date;
+use crate::{
+    Fetcher, FetcherErrors, Sites, Strip, StripType,
+    mirror::{MirrorEntry, load_manifest},
+};
+
+/// Browses a mirror saved by [`crate::mirror::mirror`] without any network access.
+///
+/// Strips that failed to mirror leave holes in the archive, Prev and Next skip them.
+pub(super) struct LocalFetcher {
+    site: Sites,
+    dir: PathBuf,
+    /// Sorted by `idx`, oldest strip first
+    entries: Vec<MirrorEntry>,
+}
+
+impl LocalFetcher {
+    pub(super) async fn new(site: Sites, dir: &Path) -> Result<Self> {
+        let mut fetcher = Self {
+            site,
+            dir: std::path::absolute(dir)?,
+            entries: Vec::new(),
+        };
+        fetcher.reload().await?;
+        Ok(fetcher)
+    }
+
+    fn strip(&self, pos: usize) -> Result<Strip> {
+        let Some(entry) = self.entries.get(pos) else {
+            bail!(FetcherErrors::OutOfRange)
+        };
+        let strip_type = StripType::at(pos, self.entries.len());
+        Ok(Strip::new(
+            self.site,
+            entry.title.clone(),
+            self.file_url(&entry.file),
+            entry.idx,
+            strip_type,
+        )
+        .with_alt_text(entry.alt_text.clone())
+        .with_hover_text(entry.hover_text.clone())
+        .with_published(entry.published)
+        .with_extra_urls(
+            entry
+                .extra_files
+                .iter()
+                .map(|file| self.file_url(file))
+                .collect(),
+        ))
+    }
+
+    fn file_url(&self, file: &str) -> String {
+        format!("file://{}", self.dir.join(file).display())
+    }
+
+    /// Position of the first entry whose `idx` is not below `idx`
+    fn position(&self, idx: usize) -> usize {
+        self.entries.partition_point(|entry| entry.idx < idx)
+    }
+}
+
+#[async_trait]
+impl Fetcher for LocalFetcher {
+    async fn reload(&mut self) -> Result<()> {
+        let mut entries = load_manifest(&self.dir).await?;
+        entries.retain(|entry| self.dir.join(&entry.file).is_file());
+        if entries.is_empty() {
+            bail!(FetcherErrors::EmptyArchive)
+        }
+        self.entries = entries;
+        Ok(())
+    }
+
+    async fn refresh(&mut self) -> Result<usize> {
+        let known = self.entries.len();
+        self.reload().await?;
+        Ok(self.entries.len().saturating_sub(known))
+    }
+
+    async fn last(&self) -> Result<Strip> {
+        self.strip(self.entries.len().wrapping_sub(1))
+    }
+
+    async fn random(&self) -> Result<Strip> {
+        if self.entries.is_empty() {
+            bail!(FetcherErrors::EmptyArchive)
+        }
+        self.strip(rng().random_range(0..self.entries.len()))
+    }
+
+    async fn get(&self, idx: usize) -> Result<Strip> {
+        let pos = self.position(idx);
+        match self.entries.get(pos) {
+            Some(entry) if entry.idx == idx => self.strip(pos),
+            _ => bail!(FetcherErrors::OutOfRange),
+        }
+    }
+
+    async fn next(&self, idx: usize) -> Result<Strip> {
+        self.strip(self.position(idx + 1))
+    }
+
+    async fn prev(&self, idx: usize) -> Result<Strip> {
+        match self.position(idx).checked_sub(1) {
+            Some(pos) => self.strip(pos),
+            None => bail!(FetcherErrors::OutOfRange),
+        }
+    }
+
+    async fn by_date(&self, date: NaiveDate) -> Result<Strip> {
+        let dates = self
+            .entries
+            .iter()
+            .map(|entry| (entry.idx, entry.published));
+        match nearest_by_date(dates, date) {
+            Some(idx) => self.get(idx).await,
+            None => bail!(FetcherErrors::OutOfRange),
+        }
+    }
+
+    fn page_url(&self, idx: usize) -> Option<String> {
+        let pos = self.position(idx);
+        self.entries
+            .get(pos)
+            .filter(|entry| entry.idx == idx)
+            .and_then(|entry| entry.page.clone())
+    }
+
+    fn strip_count(&self) -> Option<usize> {
+        self.entries.last().map(|entry| entry.idx + 1)
+    }
+}
@@ -1,39 +1,68 @@
 mod achewood;
 mod butter_safe;
 mod buttercup_festival;
-mod cad_comics;
 mod cat_and_girl;
 mod cornet_comics;
 mod diesel_sweeties_1_0;
 mod diesel_sweeties_3_0;
 mod dinosaur_comics;
+mod generic;
+mod generic_feed;
 mod gt2;
 mod gunnerkrigg_court;
 mod joy_of_tech;
 mod js_power_hour;
+mod local;
 mod monkey_user;
 mod oglaf;
 mod phd;
-mod poorly_drawn_lines;
 mod questionable_content;
 mod softer_world;
+mod syndication;
 mod three_word_phrase;
 mod turnoff_us;
+mod wordpress;
 mod work_chronicles;
 mod xkcd;
 
+use std::{
+    cmp::Reverse,
+    collections::HashSet,
+    path::Path,
+    sync::{Arc, Mutex},
+};
+
 use anyhow::{Result, bail};
 use async_trait::async_trait;
+use chrono::{DateTime, NaiveDate};
 use rand::{RngExt, rng};
+use rss::Item;
 use scraper::{Html, Selector};
+use thiserror::Error;
 
-use crate::{Fetcher, FetcherErrors, Sites, Strip, StripType, Url};
+use crate::{
+    Fetcher, FetcherErrors, Sites, Strip, StripType, Url,
+    cache::{IndexCache, PostImages},
+    custom,
+    http::{HttpClient, Validators},
+};
 
 struct FetcherImpl {
     site: Sites,
     posts: Option<Vec<Strip>>,
+    base_url: Option<String>,
+    http: HttpClient,
+    /// Validators of the archive pages, shared with the fetchers used to refresh
+    validators: Arc<Mutex<Validators>>,
+    /// Images found while listing the posts, shared like `validators`
+    images: Arc<Mutex<PostImages>>,
 }
 
+/// The archive page didn't change since its validator was recorded
+#[derive(Error, Debug)]
+#[error("The archive did not change")]
+struct NotModified;
+
 #[async_trait]
 impl Fetcher for FetcherImpl {
     async fn reload(&mut self) -> Result<()> {
@@ -45,7 +74,7 @@ impl Fetcher for FetcherImpl {
             Sites::Xkcd => self.reload_xkcd().await,
             Sites::Oglaf => self.reload_oglaf().await,
             Sites::DinosaurComics => self.reload_dinosaur_comics().await,
-            Sites::CadComics => self.reload_cmd().await,
+            Sites::CadComics => self.reload_wordpress().await,
             Sites::JoyOfTech => self.reload_joy_of_tech().await,
             Sites::GoodTechThings => self.reload_gt2().await,
             Sites::ThreeWordPhrase => self.reload_three_word_phrase().await,
@@ -59,56 +88,330 @@ impl Fetcher for FetcherImpl {
             Sites::CatAndGirl => self.reload_cat_and_girl().await,
             Sites::DieselSweeties1_0 => self.reload_diesel_sweeties_1_0().await,
             Sites::DieselSweeties3_0 => self.reload_diesel_sweeties_3_0().await,
-            Sites::PoorlyDrawnLines => self.reload_poorly_drawn_lines().await,
+            Sites::PoorlyDrawnLines => self.reload_wordpress().await,
             Sites::PiledHigherAndDeeper => self.reload_phd().await,
             Sites::GunnerkriggCourt => self.reload_gunnerkrigg_court().await,
+            Sites::Custom(site) if site.feed().is_some() => self.reload_generic_feed().await,
+            Sites::Custom(site) if site.is_wordpress() => self.reload_wordpress().await,
+            Sites::Custom(site) => self.reload_generic(site).await,
         };
-        self.set_strip_type();
-        res
+        self.index_posts();
+        match res {
+            // The posts at hand are up to date
+            Err(err) if err.is::<NotModified>() && self.posts.is_some() => Ok(()),
+            res => res,
+        }
+    }
+
+    async fn refresh(&mut self) -> Result<usize> {
+        let Some(known) = self.posts.take().filter(|known| !known.is_empty()) else {
+            // Nothing to fall back on if the archive turned out to be unchanged
+            self.validators.lock().unwrap().clear();
+            self.reload().await?;
+            return Ok(self.posts.as_ref().map(Vec::len).unwrap_or_default());
+        };
+
+        let validators = self.validators.lock().unwrap().clone();
+        let res = match self.site {
+            Sites::Xkcd => self.refresh_xkcd(&known[0]).await,
+            Sites::WorkChronicles => self.refresh_work_chronicles(&known[0]).await,
+            Sites::CadComics | Sites::PoorlyDrawnLines => self.refresh_wordpress(&known[0]).await,
+            Sites::Custom(site) if site.is_wordpress() => self.refresh_wordpress(&known[0]).await,
+            _ => self.refresh_full().await,
+        };
+        let newer = match res {
+            Ok(newer) => newer,
+            Err(err) if err.is::<NotModified>() => {
+                self.posts = Some(known);
+                return Ok(0);
+            }
+            Err(err) => {
+                // Pages fetched before the failure must not be skipped next time
+                *self.validators.lock().unwrap() = validators;
+                self.posts = Some(known);
+                return Err(err);
+            }
+        };
+
+        let known_urls: HashSet<_> = known.iter().map(|strip| strip.url.as_str()).collect();
+        let mut posts: Vec<_> = newer
+            .into_iter()
+            .filter(|strip| !known_urls.contains(strip.url.as_str()))
+            .collect();
+        let added = posts.len();
+        posts.extend(known);
+        self.posts = Some(posts);
+        self.index_posts();
+        Ok(added)
     }
 
     async fn last(&self) -> Result<Strip> {
         match self.last_content() {
             Some(content) => self.parse_content(content).await,
-            None => bail!(FetcherErrors::Error404),
+            None => bail!(FetcherErrors::EmptyArchive),
         }
     }
 
     async fn random(&self) -> Result<Strip> {
         match self.random_content() {
             Some(content) => self.parse_content(content).await,
-            None => bail!(FetcherErrors::Error404),
+            None => bail!(FetcherErrors::EmptyArchive),
+        }
+    }
+
+    async fn get(&self, idx: usize) -> Result<Strip> {
+        match self.idx_content(idx) {
+            Some(content) => self.parse_content(content).await,
+            None => bail!(FetcherErrors::OutOfRange),
         }
     }
 
     async fn next(&self, idx: usize) -> Result<Strip> {
+        match self.idx_content(idx + 1) {
+            Some(content) => self.parse_content(content).await,
+            None => bail!(FetcherErrors::OutOfRange),
+        }
+    }
+
+    async fn prev(&self, idx: usize) -> Result<Strip> {
         if idx == 0 {
-            bail!(FetcherErrors::Error404)
+            bail!(FetcherErrors::OutOfRange)
         }
 
         match self.idx_content(idx - 1) {
             Some(content) => self.parse_content(content).await,
-            None => bail!(FetcherErrors::Error404),
+            None => bail!(FetcherErrors::OutOfRange),
         }
     }
 
-    async fn prev(&self, idx: usize) -> Result<Strip> {
-        match self.idx_content(idx + 1) {
-            Some(content) => self.parse_content(content).await,
-            None => bail!(FetcherErrors::Error404),
+    async fn by_date(&self, date: NaiveDate) -> Result<Strip> {
+        let posts = self.posts.as_deref().unwrap_or_default();
+        let dates = posts.iter().map(|post| (post.idx, post.published));
+        match nearest_by_date(dates, date) {
+            Some(idx) => self.get(idx).await,
+            None => bail!(FetcherErrors::OutOfRange),
         }
     }
+
+    fn page_url(&self, idx: usize) -> Option<String> {
+        self.idx_content(idx).map(|content| content.url.clone())
+    }
+
+    fn strip_count(&self) -> Option<usize> {
+        self.posts.as_ref().map(Vec::len)
+    }
 }
 
+/// `url` resolved against `base`, left as is when either isn't a valid url
+fn absolute_url(base: &str, url: &str) -> String {
+    reqwest::Url::parse(base)
+        .and_then(|base| base.join(url))
+        .map_or_else(|_| url.to_owned(), String::from)
+}
+
+/// `idx` of the strip published closest to `date` out of `idx` and publication date pairs,
+/// the newer strip on ties
+fn nearest_by_date(
+    dates: impl IntoIterator<Item = (usize, Option<NaiveDate>)>,
+    date: NaiveDate,
+) -> Option<usize> {
+    dates
+        .into_iter()
+        .filter_map(|(idx, published)| {
+            let distance = (published? - date).num_days().abs();
+            Some((distance, Reverse(idx)))
+        })
+        .min()
+        .map(|(_, Reverse(idx))| idx)
+}
+
+type BoxedFetcher = Box<dyn Fetcher + Send + Sync>;
+
 pub async fn build_fetcher(site: Sites) -> Option<impl Fetcher> {
-    let posts = None;
-    let mut fetcher = FetcherImpl { site, posts };
-    fetcher.reload().await.ok().map(|_| fetcher)
+    or_registered(site, build_fetcher_inner(site, None, HttpClient::shared()))
+        .await
+        .ok()
+}
+
+/// Build a fetcher whose requests are sent to `base_url` instead of the site origin.
+///
+/// Useful to point a scraper at a mirror or at a local server replaying recorded pages.
+/// Sites registered with their own fetcher ignore `base_url`.
+pub async fn build_fetcher_with_base_url(
+    site: Sites,
+    base_url: &str,
+) -> Option<impl Fetcher + use<>> {
+    let base_url = Some(base_url.trim_end_matches('/').to_owned());
+    or_registered(
+        site,
+        build_fetcher_inner(site, base_url, HttpClient::shared()),
+    )
+    .await
+    .ok()
+}
+
+/// Build a fetcher reusing the archive index stored in `cache`.
+///
+/// The site is contacted only when the cached index is missing or expired, in which case the index is
+/// refreshed incrementally. If that fails the expired index is still used, so archives already seen
+/// stay browsable offline, otherwise the error tells why the archive could not be loaded. Sites
+/// registered with their own fetcher are not cached.
+pub async fn build_cached_fetcher(
+    site: Sites,
+    cache: &IndexCache,
+) -> Result<impl Fetcher + use<>, FetcherErrors> {
+    let builtin = build_cached_fetcher_inner(site, None, HttpClient::shared(), cache);
+    or_registered(site, builtin).await
+}
+
+/// Subscribe to the RSS or Atom feed at `url`, the site following it is listed next to the others
+/// from now on. Subscribing twice to the same feed gives back the same site.
+pub async fn subscribe_feed(url: &str) -> Result<Sites> {
+    let url = url.trim();
+    if let Some(site) = custom::feed_site(url) {
+        return Ok(site);
+    }
+    let feed = generic_feed::discover_feed(&HttpClient::shared(), url).await?;
+    custom::subscribe(feed)
+}
+
+/// Fetcher browsing the mirror of `site` saved in `dir`, `None` when there is nothing to browse
+pub async fn build_local_fetcher(site: Sites, dir: &Path) -> Option<impl Fetcher + use<>> {
+    local::LocalFetcher::new(site, dir).await.ok()
+}
+
+/// Fetcher registered for `site` by another crate, loaded, or else the one built by `builtin`
+async fn or_registered(
+    site: Sites,
+    builtin: impl Future<Output = Result<FetcherImpl, FetcherErrors>>,
+) -> Result<BoxedFetcher, FetcherErrors> {
+    let registered = match site {
+        Sites::Custom(site) => site.build_fetcher(),
+        _ => None,
+    };
+    let Some(mut fetcher) = registered else {
+        return builtin
+            .await
+            .map(|fetcher| Box::new(fetcher) as BoxedFetcher);
+    };
+    fetcher.reload().await?;
+    Ok(fetcher)
+}
+
+async fn build_fetcher_inner(
+    site: Sites,
+    base_url: Option<String>,
+    http: HttpClient,
+) -> Result<FetcherImpl, FetcherErrors> {
+    let mut fetcher = FetcherImpl::new(site, base_url, http).ok_or(FetcherErrors::Unavailable)?;
+    fetcher.reload().await?;
+    Ok(fetcher)
+}
+
+async fn build_cached_fetcher_inner(
+    site: Sites,
+    base_url: Option<String>,
+    http: HttpClient,
+    cache: &IndexCache,
+) -> Result<FetcherImpl, FetcherErrors> {
+    let cached = cache.load(site).await;
+    let fresh = cached.as_ref().is_some_and(|cached| cache.is_fresh(cached));
+    let mut fetcher = FetcherImpl::new(site, base_url, http).ok_or(FetcherErrors::Unavailable)?;
+    if let Some(cached) = cached {
+        fetcher.posts = Some(cached.posts);
+        fetcher.validators = Arc::new(Mutex::new(cached.validators));
+        fetcher.images = Arc::new(Mutex::new(cached.images));
+    }
+    fetcher.index_posts();
+
+    if !fresh {
+        match (fetcher.refresh().await, fetcher.posts.as_deref()) {
+            (Ok(_), Some(posts)) => {
+                let validators = fetcher.validators.lock().unwrap().clone();
+                let images = fetcher.images.lock().unwrap().clone();
+                let _ = cache.store(site, posts, &validators, &images).await;
+            }
+            // Nothing cached to fall back on
+            (Err(err), None) => return Err(err.into()),
+            _ => {}
+        }
+    }
+    match fetcher.posts {
+        Some(_) => Ok(fetcher),
+        None => Err(FetcherErrors::Unavailable),
+    }
 }
 
 impl FetcherImpl {
-    fn set_strip_type(&mut self) {
+    fn new(site: Sites, base_url: Option<String>, http: HttpClient) -> Option<Self> {
+        let http = match site {
+            // Something is wrong with the certificate of the site
+            Sites::DieselSweeties1_0 | Sites::DieselSweeties3_0 => {
+                http.accepting_invalid_certs().ok()?
+            }
+            // Browsed by the fetcher registered along with the site
+            Sites::Custom(custom) if custom.definition().is_none() && custom.feed().is_none() => {
+                return None;
+            }
+            _ => http,
+        };
+        Some(Self {
+            site,
+            posts: None,
+            base_url,
+            http,
+            validators: Arc::default(),
+            images: Arc::default(),
+        })
+    }
+
+    /// Archive/feed url of the site, rebased on `base_url` when set
+    fn fetch_url(&self) -> String {
+        self.rebase(self.site.fetch_url())
+    }
+
+    /// Homepage of the site including the scheme, rebased on `base_url` when set
+    fn homepage_url(&self) -> String {
+        self.rebase(&format!("https://{}", self.site.homepage()))
+    }
+
+    fn rebase(&self, url: &str) -> String {
+        match self.base_url.as_deref() {
+            Some(base_url) => {
+                let path = url
+                    .split_once("://")
+                    .and_then(|(_, rest)| rest.find('/').map(|pos| &rest[pos..]))
+                    .unwrap_or_default();
+                format!("{base_url}{path}")
+            }
+            None => url.to_owned(),
+        }
+    }
+
+    /// Fetch the whole archive again, used to refresh sites without a cheaper strategy
+    async fn refresh_full(&self) -> Result<Vec<Strip>> {
+        let mut fetcher = FetcherImpl {
+            site: self.site,
+            posts: None,
+            base_url: self.base_url.clone(),
+            http: self.http.clone(),
+            validators: self.validators.clone(),
+            images: self.images.clone(),
+        };
+        fetcher.reload().await?;
+        Ok(fetcher.posts.unwrap_or_default())
+    }
+
+    /// Number the posts starting from the oldest one, so that `idx` values don't change when newer
+    /// strips are added, and mark the boundaries of the archive
+    fn index_posts(&mut self) {
         if let Some(data) = self.posts.as_deref_mut() {
+            let len = data.len();
+            data.iter_mut().enumerate().for_each(|(pos, strip)| {
+                strip.idx = len - 1 - pos;
+                strip.strip_type = StripType::Unknown;
+            });
+
             if let Some(elem) = data.first_mut() {
                 elem.strip_type = StripType::First;
             }
@@ -137,7 +440,11 @@ impl FetcherImpl {
     }
 
     fn idx_content(&self, idx: usize) -> Option<&Strip> {
-        self.posts.as_ref().and_then(|data| data.get(idx))
+        self.posts.as_ref().and_then(|data| {
+            data.len()
+                .checked_sub(idx + 1)
+                .and_then(|pos| data.get(pos))
+        })
     }
 
     async fn parse_content(&self, content: &Strip) -> Result<Strip> {
@@ -149,7 +456,7 @@ impl FetcherImpl {
             Sites::Xkcd => self.parse_xkcd_content(content).await,
             Sites::Oglaf => self.parse_oglaf_content(content).await,
             Sites::DinosaurComics => self.parse_dinosaur_comics_content(content).await,
-            Sites::CadComics => self.parse_cad_content(content).await,
+            Sites::CadComics => self.parse_wordpress_content(content).await,
             Sites::JoyOfTech => self.parse_joy_of_tech_content(content).await,
             Sites::GoodTechThings => self.parse_gt2_content(content).await,
             Sites::ThreeWordPhrase => self.parse_three_word_phrase_content(content).await,
@@ -163,12 +470,43 @@ impl FetcherImpl {
             Sites::CatAndGirl => self.parse_cat_and_girl_content(content).await,
             Sites::DieselSweeties1_0 => self.parse_diesel_sweeties_1_0_content(content).await,
             Sites::DieselSweeties3_0 => self.parse_diesel_sweeties_3_0_content(content).await,
-            Sites::PoorlyDrawnLines => self.parse_poorly_drawn_lines_content(content).await,
+            Sites::PoorlyDrawnLines => self.parse_wordpress_content(content).await,
             Sites::PiledHigherAndDeeper => self.parse_phd_content(content).await,
             Sites::GunnerkriggCourt => self.parse_gunnerkrigg_court_content(content).await,
+            Sites::Custom(site) if site.feed().is_some() => {
+                self.parse_generic_feed_content(content).await
+            }
+            Sites::Custom(site) if site.is_wordpress() => {
+                self.parse_wordpress_content(content).await
+            }
+            Sites::Custom(site) => self.parse_generic_content(site, content).await,
         }
     }
 
+    /// Archive page or feed at `url`, failing with `NotModified` when it didn't change since its
+    /// validator was recorded
+    async fn fetch_index(&self, url: String) -> Result<reqwest::Response> {
+        let validator = self.validators.lock().unwrap().get(&url).cloned();
+        let Some((res, validator)) = self.http.get_if_modified(&url, validator.as_ref()).await?
+        else {
+            bail!(NotModified)
+        };
+        let mut validators = self.validators.lock().unwrap();
+        match validator {
+            Some(validator) => validators.insert(url, validator),
+            None => validators.remove(&url),
+        };
+        Ok(res)
+    }
+
+    async fn index_text(&self, url: String) -> Result<String> {
+        Ok(self.fetch_index(url).await?.text().await?)
+    }
+
+    async fn index_bytes(&self, url: String) -> Result<Vec<u8>> {
+        Ok(self.fetch_index(url).await?.bytes().await?.to_vec())
+    }
+
     fn parse_first_occurrence_blocking(data: &str, selector: &str, attr: &str) -> Option<String> {
         let frag = Html::parse_document(data);
         let selector = Selector::parse(selector).unwrap();
@@ -181,16 +519,39 @@ impl FetcherImpl {
         )
     }
 
+    /// `attr` of every element matching `selector`, in document order
+    fn parse_all_occurrences_blocking(data: &str, selector: &str, attr: &str) -> Vec<String> {
+        let frag = Html::parse_document(data);
+        let selector = Selector::parse(selector).unwrap();
+        frag.select(&selector)
+            .filter_map(|elem| elem.value().attr(attr))
+            .map(str::to_string)
+            .collect()
+    }
+
+    /// Day an RSS item was published, from its RFC 2822 `pubDate`
+    fn rss_date(item: &Item) -> Option<NaiveDate> {
+        DateTime::parse_from_rfc2822(item.pub_date()?.trim())
+            .ok()
+            .map(|date| date.date_naive())
+    }
+
+    /// Alt and title (hover) texts of the first element matching `selector`, blank ones left out
+    fn parse_image_texts_blocking(data: &str, selector: &str) -> (Option<String>, Option<String>) {
+        let text = |attr| {
+            Self::parse_first_occurrence_blocking(data, selector, attr)
+                .map(|text| text.trim().to_owned())
+                .filter(|text| !text.is_empty())
+        };
+        (text("alt"), text("title"))
+    }
+
     fn parse_meta_content_blocking(&self, data: String, property: &str) -> Option<String> {
         let frag = Html::parse_document(&data);
         let selector = Selector::parse("meta").unwrap();
         frag.select(&selector)
             .filter(|elem| elem.value().attr("property") == Some(property))
-            .map(|elem| {
-                elem.attr("content")
-                    .unwrap()
-                    .replace(self.site.fetch_url(), "")
-            })
+            .map(|elem| elem.attr("content").unwrap().replace(&self.fetch_url(), ""))
             .next_back()
     }
 
@@ -201,3 +562,712 @@ impl FetcherImpl {
             .for_each(|(idx, strip)| strip.idx = idx);
     }
 }
+
+#[cfg(test)]
+pub(crate) mod fixture;
+
+#[cfg(test)]
+mod test {
+    use std::time::Duration;
+
+    use anyhow::Result;
+    use chrono::NaiveDate;
+
+    use super::{
+        FetcherImpl, build_cached_fetcher_inner, build_fetcher, build_fetcher_inner,
+        build_local_fetcher, custom, fixture::FixtureServer, generic_feed,
+    };
+    use crate::{
+        Fetcher, FetcherErrors, Sites, Strip,
+        cache::{ImageCache, IndexCache},
+        custom::{SiteDefinition, SiteInfo, register, register_fetcher},
+        http::{HttpClient, HttpSettings},
+        mirror::{MANIFEST, MirrorEntry},
+    };
+
+    /// Client neither retrying nor spacing requests, fixtures answer at once
+    fn fixture_client() -> HttpClient {
+        let settings = HttpSettings {
+            retries: 0,
+            host_interval: 0,
+            ..Default::default()
+        };
+        HttpClient::new(settings).unwrap()
+    }
+
+    async fn fixture_fetcher(
+        site: Sites,
+        dir: &str,
+        routes: &[(&str, &str)],
+    ) -> (FixtureServer, FetcherImpl) {
+        let server = FixtureServer::start(dir, routes).await;
+        let fetcher = build_fetcher_inner(site, Some(server.url().to_owned()), fixture_client())
+            .await
+            .expect("reload against fixtures failed");
+        (server, fetcher)
+    }
+
+    async fn assert_last(fetcher: &FetcherImpl, title: &str, url: &str) -> Strip {
+        let strip = fetcher.last().await.unwrap();
+        assert_eq!(strip.title, title);
+        assert_eq!(strip.url, url);
+        assert!(strip.is_last());
+        assert!(fetcher.random().await.is_ok());
+        strip
+    }
+
+    #[tokio::test]
+    async fn test_fixture_turnoff_us() {
+        let routes = [("/all", "all.html"), ("/geek/*", "post.html")];
+        let (server, fetcher) = fixture_fetcher(Sites::TurnoffUs, "turnoff_us", &routes).await;
+        let url = format!("{}/uploads/comic.png", server.url());
+        assert_last(&fetcher, "New Comic", &url).await;
+    }
+
+    #[tokio::test]
+    async fn test_fixture_monkey_user() {
+        let routes = [("/index.xml", "index.xml"), ("/2024/*", "post.html")];
+        let (server, fetcher) = fixture_fetcher(Sites::MonkeyUser, "monkey_user", &routes).await;
+        let url = format!("{}/2024/comic.png", server.url());
+        let strip = assert_last(&fetcher, "Newest", &url).await;
+        assert_eq!(strip.published, NaiveDate::from_ymd_opt(2024, 3, 12));
+
+        let date = |day| NaiveDate::from_ymd_opt(2024, 2, day).unwrap();
+        assert_eq!(fetcher.by_date(date(10)).await.unwrap().title, "Oldest");
+        assert_eq!(fetcher.by_date(date(29)).await.unwrap().title, "Newest");
+    }
+
+    #[tokio::test]
+    async fn test_fixture_monkey_user_atom() {
+        let routes = [("/index.xml", "index.atom"), ("/2024/*", "post.html")];
+        let (server, fetcher) = fixture_fetcher(Sites::MonkeyUser, "monkey_user", &routes).await;
+        let url = format!("{}/2024/comic.png", server.url());
+        let strip = assert_last(&fetcher, "Newest", &url).await;
+        assert_eq!(strip.published, NaiveDate::from_ymd_opt(2024, 3, 12));
+        // Published rather than updated
+        let strip = fetcher.get(0).await.unwrap();
+        assert_eq!(strip.published, NaiveDate::from_ymd_opt(2024, 2, 1));
+    }
+
+    #[tokio::test]
+    async fn test_fixture_cornet_comics() {
+        let routes = [("/", "index.html")];
+        for site in [Sites::BonkersWorld, Sites::Goomics] {
+            let (server, fetcher) = fixture_fetcher(site, "cornet_comics", &routes).await;
+            let url = format!("{}/comics/new.png", server.url());
+            assert_last(&fetcher, "New Strip", &url).await;
+        }
+    }
+
+    #[tokio::test]
+    async fn test_fixture_xkcd() {
+        let routes = [("/", "index.html"), ("/*", "comic.html")];
+        let (_server, fetcher) = fixture_fetcher(Sites::Xkcd, "xkcd", &routes).await;
+        let url = "https://imgs.xkcd.com/comics/fixture.png";
+        let strip = assert_last(&fetcher, "3", url).await;
+        assert_eq!(strip.alt_text.as_deref(), Some("Fixture"));
+        assert_eq!(strip.hover_text.as_deref(), Some("hover joke"));
+
+        let prev = fetcher.prev(strip.idx).await.unwrap();
+        assert_eq!(prev.title, "2");
+        let next = fetcher.next(prev.idx).await.unwrap();
+        assert_eq!(next.title, "3");
+        assert!(fetcher.next(strip.idx).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_fetcher_errors() {
+        // Comic 2 lost its image, comic 1 is not served at all
+        let routes = [("/", "index.html"), ("/2", "index.html")];
+        let (_server, fetcher) = fixture_fetcher(Sites::Xkcd, "xkcd", &routes).await;
+        let error = async |strip: Result<Strip>| FetcherErrors::from(strip.unwrap_err());
+
+        assert_eq!(
+            error(fetcher.get(1).await).await,
+            FetcherErrors::layout_changed("og:image")
+        );
+        assert_eq!(
+            error(fetcher.get(0).await).await,
+            FetcherErrors::HttpStatus(404)
+        );
+        assert_eq!(
+            error(fetcher.next(2).await).await,
+            FetcherErrors::OutOfRange
+        );
+    }
+
+    #[tokio::test]
+    async fn test_fixture_dinosaur_comics() {
+        let routes = [
+            ("/archive.php", "archive.html"),
+            ("/index.php", "comic.html"),
+        ];
+        let (server, fetcher) =
+            fixture_fetcher(Sites::DinosaurComics, "dinosaur_comics", &routes).await;
+        let url = format!("{}/comics/comic2-2.png", server.url());
+        let strip = assert_last(&fetcher, "January 2nd, 2024 - new comic", &url).await;
+        assert_eq!(strip.hover_text.as_deref(), Some("hidden joke"));
+        assert_eq!(strip.alt_text, None);
+    }
+
+    #[tokio::test]
+    async fn test_fixture_oglaf() {
+        let routes = [
+            ("/feeds/rss", "rss.xml"),
+            ("/newest/2/", "story_2.html"),
+            ("/newest/", "story.html"),
+            ("/*", "page.html"),
+        ];
+        let (_server, fetcher) = fixture_fetcher(Sites::Oglaf, "oglaf", &routes).await;
+        let url = "https://media.oglaf.com/comic/fixture.jpg";
+        let strip = assert_last(&fetcher, "Newest", url).await;
+        assert_eq!(strip.hover_text.as_deref(), Some("hover"));
+        // The second page links back to the first one, which ends the story
+        assert_eq!(
+            strip.extra_urls,
+            ["https://media.oglaf.com/comic/fixture-2.jpg"]
+        );
+        let oldest = fetcher.prev(strip.idx).await.unwrap();
+        assert!(oldest.extra_urls.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_fixture_cad_comics() {
+        let routes = [
+            ("/wp-json/wp/v2/posts?page=1&*", "posts.json"),
+            // No `X-WP-TotalPages`, walking stops at the 400 past the last page
+            ("/wp-json/wp/v2/posts?page=2&*", "invalid_page.json"),
+            ("/comic/*", "comic.html"),
+        ];
+        let (_server, fetcher) = fixture_fetcher(Sites::CadComics, "cad_comics", &routes).await;
+        // Neither featured image nor comic in the content, the image comes from the page
+        let url = "https://cad-comic.com/wp-content/uploads/fixture.jpg";
+        let strip = assert_last(&fetcher, "Newest", url).await;
+        assert_eq!(strip.published, NaiveDate::from_ymd_opt(2024, 3, 12));
+        let strip = fetcher.get(0).await.unwrap();
+        assert_eq!(
+            strip.url,
+            "https://cad-comic.com/wp-content/uploads/oldest.jpg"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_fixture_joy_of_tech() {
+        let routes = [
+            ("/joyoftech/jotblog", "jotblog.html"),
+            ("/joyoftech/joyarchives/*", "comic.html"),
+        ];
+        let (server, fetcher) = fixture_fetcher(Sites::JoyOfTech, "joy_of_tech", &routes).await;
+        let url = format!("{}/joyoftech/joyimages/2900.png", server.url());
+        assert_last(&fetcher, "Joy of Fixture", &url).await;
+    }
+
+    #[tokio::test]
+    async fn test_fixture_good_tech_things() {
+        let routes = [("/rss/", "rss.xml")];
+        let (_server, fetcher) = fixture_fetcher(Sites::GoodTechThings, "gt2", &routes).await;
+        let url = "https://www.goodtechthings.com/content/images/newest.png";
+        assert_last(&fetcher, "Newest", url).await;
+    }
+
+    #[tokio::test]
+    async fn test_fixture_three_word_phrase() {
+        let routes = [("/archive.htm", "archive.htm"), ("/*", "comic.htm")];
+        let (server, fetcher) =
+            fixture_fetcher(Sites::ThreeWordPhrase, "three_word_phrase", &routes).await;
+        let url = format!("{}/newest.gif", server.url());
+        assert_last(&fetcher, "Newest", &url).await;
+    }
+
+    #[tokio::test]
+    async fn test_fixture_a_softer_world() {
+        let routes = [
+            ("/archive.php", "archive.php"),
+            ("/index.php", "comic.html"),
+        ];
+        let (server, fetcher) = fixture_fetcher(Sites::ASofterWorld, "softer_world", &routes).await;
+        let url = format!("{}/clean/fixture.jpg", server.url());
+        assert_last(&fetcher, "a softer fixture", &url).await;
+    }
+
+    #[tokio::test]
+    async fn test_fixture_generic() {
+        let path = std::path::Path::new("tests/fixtures/generic/site.toml");
+        let site = register(SiteDefinition::load(path).unwrap()).unwrap();
+        let routes = [("/archive/", "archive.html"), ("/strips/*", "strip.html")];
+        let (server, fetcher) = fixture_fetcher(site, "generic", &routes).await;
+        let url = format!("{}/strips/images/top.png", server.url());
+        let strip = assert_last(&fetcher, "Newest", &url).await;
+        assert_eq!(
+            strip.extra_urls,
+            [format!("{}/images/bottom.png", server.url())]
+        );
+        assert_eq!(strip.alt_text.as_deref(), Some("Top panel"));
+        assert_eq!(strip.hover_text.as_deref(), Some("Hidden joke"));
+
+        // Listed oldest first
+        let first = fetcher.get(0).await.unwrap();
+        assert_eq!(first.title, "Oldest");
+        assert_eq!(
+            fetcher.page_url(0),
+            Some(format!("{}/strips/1", server.url()))
+        );
+        assert_eq!(
+            fetcher.page_url(1),
+            Some(format!("{}/strips/2", server.url()))
+        );
+    }
+
+    #[tokio::test]
+    async fn test_fixture_comic_easel() {
+        let path = std::path::Path::new("tests/fixtures/comic_easel/site.toml");
+        let site = register(SiteDefinition::load(path).unwrap()).unwrap();
+        let routes = [
+            ("/wp-json/wp/v2/comic?page=1&*", "comic.json"),
+            ("/comic/*", "comic.html"),
+        ];
+        let (server, fetcher) = fixture_fetcher(site, "comic_easel", &routes).await;
+        let url = format!("{}/wp-content/uploads/newest.png", server.url());
+        let strip = assert_last(&fetcher, "Newest", &url).await;
+        assert_eq!(strip.published, NaiveDate::from_ymd_opt(2024, 5, 2));
+        // Nothing in the content, the image comes from the page
+        let strip = fetcher.get(0).await.unwrap();
+        assert_eq!(
+            strip.url,
+            format!("{}/wp-content/uploads/oldest.png", server.url())
+        );
+    }
+
+    #[tokio::test]
+    async fn test_fixture_generic_feed() {
+        let routes = [("/feed.xml", "feed.xml"), ("/strips/*", "page.html")];
+        let server = FixtureServer::start("generic_feed", &routes).await;
+        let url = format!("{}/feed.xml", server.url());
+        let feed = generic_feed::discover_feed(&fixture_client(), &url)
+            .await
+            .unwrap();
+        assert_eq!(feed.info.name, "Fixture Feed");
+        assert_eq!(feed.info.id, "fixture-feed");
+        assert_eq!(feed.info.homepage, format!("{}/", server.url()));
+        let site = custom::register_feed(feed).unwrap();
+
+        let fetcher = build_fetcher_inner(site, None, fixture_client())
+            .await
+            .unwrap();
+        let image = |path: &str| format!("{}/images/{path}", server.url());
+        let strip = assert_last(&fetcher, "Enclosure", &image("4.png")).await;
+        assert_eq!(strip.published, NaiveDate::from_ymd_opt(2024, 3, 14));
+        assert_eq!(
+            fetcher.page_url(3),
+            Some(format!("{}/strips/4", server.url()))
+        );
+
+        let strip = fetcher.get(2).await.unwrap();
+        assert_eq!(
+            (strip.url, strip.extra_urls),
+            (image("3-thumb.jpg"), vec![])
+        );
+        let strip = fetcher.get(1).await.unwrap();
+        assert_eq!(
+            (strip.url, strip.extra_urls),
+            (image("2a.gif"), vec![image("2b.gif")])
+        );
+        // Nothing in the feed, the image comes from the page
+        let strip = fetcher.get(0).await.unwrap();
+        assert_eq!((strip.title.as_str(), strip.url), ("Page", image("1.png")));
+    }
+
+    #[tokio::test]
+    async fn test_fixture_generic_feed_atom() {
+        let routes = [("/atom.xml", "atom.xml"), ("/strips/*", "page.html")];
+        let server = FixtureServer::start("generic_feed", &routes).await;
+        let url = format!("{}/atom.xml", server.url());
+        let feed = generic_feed::discover_feed(&fixture_client(), &url)
+            .await
+            .unwrap();
+        assert_eq!(feed.info.name, "Fixture Atom Feed");
+        assert_eq!(feed.info.homepage, format!("{}/", server.url()));
+        let site = custom::register_feed(feed).unwrap();
+
+        let fetcher = build_fetcher_inner(site, None, fixture_client())
+            .await
+            .unwrap();
+        let image = |path: &str| format!("{}/images/{path}", server.url());
+        let strip = assert_last(&fetcher, "Enclosure", &image("4.png")).await;
+        assert_eq!(strip.published, NaiveDate::from_ymd_opt(2024, 3, 14));
+
+        assert_eq!(fetcher.get(2).await.unwrap().url, image("3-thumb.jpg"));
+        let strip = fetcher.get(1).await.unwrap();
+        assert_eq!(
+            (strip.url, strip.extra_urls),
+            (image("2a.gif"), vec![image("2b.gif")])
+        );
+        let strip = fetcher.get(0).await.unwrap();
+        assert_eq!((strip.title.as_str(), strip.url), ("Page", image("1.png")));
+    }
+
+    #[tokio::test]
+    async fn test_registered_fetcher() {
+        let routes = [("/", "index.html"), ("/*", "comic.html")];
+        let server = FixtureServer::start("xkcd", &routes).await;
+        let base_url = server.url().to_owned();
+        let info = SiteInfo {
+            id: "registered-xkcd".to_owned(),
+            name: "Registered xkcd".to_owned(),
+            homepage: "https://xkcd.example".to_owned(),
+            nsfw: true,
+        };
+        let site = register_fetcher(info, move |_| {
+            let fetcher = FetcherImpl::new(Sites::Xkcd, Some(base_url.clone()), fixture_client());
+            Box::new(fetcher.unwrap())
+        })
+        .unwrap();
+        assert_eq!(Sites::from_id("registered-xkcd"), Some(site));
+        assert!(Sites::sites_sorted().contains(&site));
+        assert_eq!(site.to_string(), "Registered xkcd [NSFW]");
+        assert!(site.is_nsfw());
+
+        let fetcher = build_fetcher(site).await.unwrap();
+        assert_eq!(fetcher.last().await.unwrap().title, "3");
+        // Nothing to scrape without a definition
+        assert_eq!(
+            build_fetcher_inner(site, None, fixture_client())
+                .await
+                .err(),
+            Some(FetcherErrors::Unavailable)
+        );
+    }
+
+    #[tokio::test]
+    async fn test_fixture_butter_safe() {
+        let routes = [("/archive", "archive.html"), ("/2024/*", "comic.html")];
+        let (server, fetcher) = fixture_fetcher(Sites::ButterSafe, "butter_safe", &routes).await;
+        let url = format!("{}/comics/newest.jpg", server.url());
+        assert_last(&fetcher, "Butter Fixture", &url).await;
+    }
+
+    #[tokio::test]
+    async fn test_fixture_questionable_content() {
+        let routes = [("/QCRSS.xml", "QCRSS.xml")];
+        let (_server, fetcher) =
+            fixture_fetcher(Sites::QuestionableContent, "questionable_content", &routes).await;
+        let url = "https://www.questionablecontent.net/comics/5002.png";
+        assert_last(&fetcher, "Newest", url).await;
+    }
+
+    #[tokio::test]
+    async fn test_fixture_work_chronicles() {
+        let routes = [
+            ("/sitemap", "sitemap.html"),
+            ("/sitemap/2024", "sitemap_2024.html"),
+            ("/sitemap/2023", "sitemap_2023.html"),
+            ("/p/*", "post.html"),
+        ];
+        let (_server, fetcher) =
+            fixture_fetcher(Sites::WorkChronicles, "work_chronicles", &routes).await;
+        let url = "https://substackcdn.com/image/fixture.png";
+        let strip = assert_last(&fetcher, "Newest", url).await;
+        assert_eq!(
+            strip.image_urls(),
+            [url, "https://substackcdn.com/image/fixture-2.png"]
+        );
+        assert_eq!(fetcher.prev(strip.idx).await.unwrap().title, "Middle");
+    }
+
+    #[tokio::test]
+    async fn test_fixture_js_power_hour() {
+        let routes = [("/comics", "comics.html"), ("/comics/*", "comic.html")];
+        let (_server, fetcher) =
+            fixture_fetcher(Sites::JSPowerHour, "js_power_hour", &routes).await;
+        let url = "https://cdn.jspowerhour.com/fixture.png";
+        assert_last(&fetcher, "newest", url).await;
+    }
+
+    #[tokio::test]
+    async fn test_fixture_buttercup_festival() {
+        let routes = [("/", "index.html"), ("/*", "comic.htm")];
+        let (server, fetcher) =
+            fixture_fetcher(Sites::ButtercupFestival, "buttercup_festival", &routes).await;
+        let url = format!("{}/2-02.png", server.url());
+        assert_last(&fetcher, "2-02", &url).await;
+    }
+
+    #[tokio::test]
+    async fn test_fixture_achewood() {
+        let routes = [
+            ("/archive_new.html", "archive_new.html"),
+            ("/index.php", "comic.html"),
+        ];
+        let (server, fetcher) = fixture_fetcher(Sites::Achewood, "achewood", &routes).await;
+        let url = format!("{}/comic.php?date=10022001", server.url());
+        assert_last(&fetcher, "Newest", &url).await;
+    }
+
+    #[tokio::test]
+    async fn test_fixture_cat_and_girl() {
+        let routes = [("/archive", "archive.html"), ("/*", "comic.html")];
+        let (_server, fetcher) = fixture_fetcher(Sites::CatAndGirl, "cat_and_girl", &routes).await;
+        let url = "https://catandgirl.com/wp-content/uploads/fixture.png";
+        assert_last(&fetcher, "Newest", url).await;
+    }
+
+    #[tokio::test]
+    async fn test_fixture_diesel_sweeties_1_0() {
+        let routes = [("/archive/*", "comic.html")];
+        let (server, fetcher) =
+            fixture_fetcher(Sites::DieselSweeties1_0, "diesel_sweeties_1_0", &routes).await;
+        let url = format!("{}/strips/fixture.png", server.url());
+        assert_last(&fetcher, "Diesel Fixture", &url).await;
+    }
+
+    #[tokio::test]
+    async fn test_fixture_diesel_sweeties_3_0() {
+        let routes = [("/ds-unifeed.xml", "ds-unifeed.xml")];
+        let (_server, fetcher) =
+            fixture_fetcher(Sites::DieselSweeties3_0, "diesel_sweeties_3_0", &routes).await;
+        let url = "https://www.dieselsweeties.com/strips666/newest.png";
+        assert_last(&fetcher, "Newest", url).await;
+    }
+
+    #[tokio::test]
+    async fn test_fixture_poorly_drawn_lines() {
+        let routes = [
+            ("/wp-json/wp/v2/posts?page=1&*", "posts_1.json"),
+            ("/wp-json/wp/v2/posts?page=2&*", "posts_2.json"),
+            ("/comic/*", "comic.html"),
+        ];
+        let (server, fetcher) =
+            fixture_fetcher(Sites::PoorlyDrawnLines, "poorly_drawn_lines", &routes).await;
+        // Images listed by the API stay with the fetcher until the strips are resolved
+        let posts = fetcher.posts.as_deref().unwrap();
+        assert!(posts.iter().all(|post| post.extra_urls.is_empty()));
+        assert_eq!(fetcher.images.lock().unwrap().len(), 2);
+        let url = "https://poorlydrawnlines.com/wp-content/uploads/fixture.png";
+        let strip = assert_last(&fetcher, "Newest", url).await;
+        assert_eq!(strip.published, NaiveDate::from_ymd_opt(2024, 3, 12));
+        // Titles come rendered, the featured image is used when the content has no comic
+        let strip = fetcher.get(1).await.unwrap();
+        assert_eq!(strip.title, "It\u{2019}s <Featured>");
+        assert_eq!(
+            strip.url,
+            format!("{}/wp-content/uploads/featured.png", server.url())
+        );
+        // Nothing found through the API, the image comes from the page
+        let strip = fetcher.get(0).await.unwrap();
+        assert_eq!((strip.title.as_str(), strip.url.as_str()), ("Oldest", url));
+        // Walking stops at the last page told by `X-WP-TotalPages`, `random` may have scraped
+        // pages as well
+        let pages = server.requests();
+        let pages = pages.iter().filter(|path| path.starts_with("/wp-json/"));
+        assert_eq!(pages.count(), 2);
+
+        // The images are stored along with the index
+        let dir = std::env::temp_dir().join(format!("daily-strip-wp-{}", std::process::id()));
+        let cache = IndexCache::new(dir.clone(), Duration::from_secs(60));
+        let base_url = Some(server.url().to_owned());
+        build_cached_fetcher_inner(Sites::PoorlyDrawnLines, base_url, fixture_client(), &cache)
+            .await
+            .unwrap();
+        let cached = cache.load(Sites::PoorlyDrawnLines).await.unwrap();
+        assert_eq!(cached.images, *fetcher.images.lock().unwrap());
+        let _ = std::fs::remove_dir_all(dir);
+    }
+
+    #[tokio::test]
+    async fn test_fixture_phd() {
+        let routes = [
+            ("/comics/archive_list.php", "archive_list.html"),
+            ("/comics/comic.php", "comic.html"),
+        ];
+        let (_server, fetcher) = fixture_fetcher(Sites::PiledHigherAndDeeper, "phd", &routes).await;
+        let url = "https://phdcomics.com/comics/archive/phd010297s.gif";
+        assert_last(&fetcher, "Newest", url).await;
+    }
+
+    #[tokio::test]
+    async fn test_fixture_gunnerkrigg_court() {
+        let routes = [("/archives", "archives.html")];
+        let (server, fetcher) =
+            fixture_fetcher(Sites::GunnerkriggCourt, "gunnerkrigg_court", &routes).await;
+        let url = format!("{}/comics/00000003.jpg", server.url());
+        assert_last(&fetcher, "3", &url).await;
+    }
+
+    #[tokio::test]
+    async fn test_cached_index() {
+        let dir = std::env::temp_dir().join(format!("daily-strip-test-{}", std::process::id()));
+        let fresh = IndexCache::new(dir.clone(), Duration::from_secs(60));
+        let expired = IndexCache::new(dir.clone(), Duration::ZERO);
+
+        let routes = [("/", "index.html"), ("/*", "comic.html")];
+        let server = FixtureServer::start("xkcd", &routes).await;
+        let base_url = Some(server.url().to_owned());
+        let fetcher =
+            build_cached_fetcher_inner(Sites::Xkcd, base_url.clone(), fixture_client(), &fresh)
+                .await;
+        assert!(fetcher.is_ok());
+        assert_eq!(fresh.load(Sites::Xkcd).await.unwrap().posts.len(), 3);
+        drop(server);
+
+        // Site unreachable: a fresh index is used as is, an expired one as fallback
+        for cache in [&fresh, &expired] {
+            let fetcher =
+                build_cached_fetcher_inner(Sites::Xkcd, base_url.clone(), fixture_client(), cache)
+                    .await;
+            assert_eq!(fetcher.unwrap().last_content().unwrap().title, "3");
+        }
+        // Without an index to fall back on, why the site could not be loaded is told
+        let err = build_fetcher_inner(Sites::Xkcd, base_url, fixture_client()).await;
+        assert!(matches!(err.err(), Some(FetcherErrors::Network(_))));
+
+        let _ = std::fs::remove_dir_all(dir);
+    }
+
+    #[tokio::test]
+    async fn test_conditional_refresh() {
+        let dir = std::env::temp_dir().join(format!("daily-strip-etag-{}", std::process::id()));
+        let expired = IndexCache::new(dir.clone(), Duration::ZERO);
+        let routes = [("/index.xml", "index.xml"), ("/2024/*", "post.html")];
+        let server = FixtureServer::start("monkey_user", &routes).await;
+        let base_url = Some(server.url().to_owned());
+        let build = async || {
+            build_cached_fetcher_inner(
+                Sites::MonkeyUser,
+                base_url.clone(),
+                fixture_client(),
+                &expired,
+            )
+            .await
+            .unwrap()
+        };
+
+        let mut fetcher = build().await;
+        let validators = expired.load(Sites::MonkeyUser).await.unwrap().validators;
+        assert_eq!(validators.len(), 1);
+        assert_eq!(server.not_modified(), 0);
+
+        // The expired index is revalidated instead of being downloaded and parsed again
+        let revalidated = build().await;
+        assert_eq!(server.not_modified(), 1);
+        assert_eq!(revalidated.last_content().unwrap().title, "Newest");
+        assert_eq!(revalidated.posts.as_ref().unwrap().len(), 2);
+
+        assert_eq!(fetcher.refresh().await.unwrap(), 0);
+        fetcher.reload().await.unwrap();
+        assert_eq!(server.not_modified(), 3);
+        assert_eq!(fetcher.last().await.unwrap().title, "Newest");
+
+        let _ = std::fs::remove_dir_all(dir);
+    }
+
+    #[tokio::test]
+    async fn test_refresh_keeps_idx() {
+        let routes = [("/", "index.html"), ("/*", "comic.html")];
+        let (_server, mut fetcher) = fixture_fetcher(Sites::Xkcd, "xkcd", &routes).await;
+        fetcher.posts.as_mut().unwrap().remove(0);
+        fetcher.index_posts();
+        let known = fetcher.last().await.unwrap();
+        assert_eq!((known.title.as_str(), known.idx), ("2", 1));
+        // The archive gained a strip since it was fetched
+        fetcher.validators.lock().unwrap().clear();
+
+        assert_eq!(fetcher.refresh().await.unwrap(), 1);
+        let last = fetcher.last().await.unwrap();
+        assert_eq!((last.title.as_str(), last.idx), ("3", 2));
+        let prev = fetcher.prev(last.idx).await.unwrap();
+        assert_eq!((prev.title.as_str(), prev.idx), ("2", 1));
+        assert!(!prev.is_last());
+        assert_eq!(fetcher.refresh().await.unwrap(), 0);
+
+        let routes = [
+            ("/sitemap", "sitemap.html"),
+            ("/sitemap/2024", "sitemap_2024.html"),
+            ("/sitemap/2023", "sitemap_2023.html"),
+        ];
+        let (server, mut fetcher) =
+            fixture_fetcher(Sites::WorkChronicles, "work_chronicles", &routes).await;
+        fetcher.posts.as_mut().unwrap().remove(0);
+        fetcher.index_posts();
+        // A new strip changes the page of its year, `/sitemap` stays the same
+        let year_url = format!("{}/sitemap/2024", server.url());
+        assert!(
+            fetcher
+                .validators
+                .lock()
+                .unwrap()
+                .remove(&year_url)
+                .is_some()
+        );
+        let requests = server.requests().len();
+
+        // The 2024 page lists the latest known strip, older pages are skipped
+        assert_eq!(fetcher.refresh().await.unwrap(), 1);
+        assert_eq!(
+            server.requests()[requests..],
+            ["/sitemap".to_owned(), "/sitemap/2024".to_owned()]
+        );
+        assert_eq!(fetcher.last_content().unwrap().idx, 2);
+        let not_modified = server.not_modified();
+        assert_eq!(fetcher.refresh().await.unwrap(), 0);
+        assert_eq!(server.not_modified(), not_modified + 1);
+    }
+
+    #[tokio::test]
+    async fn test_local_fetcher() {
+        let dir = std::env::temp_dir().join(format!("daily-strip-local-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let image = std::fs::read("tests/fixtures/images/strip.png").unwrap();
+        // Strip 2 failed to mirror and strip 4 lost its image
+        let mut manifest = String::new();
+        for idx in [0, 1, 3, 4] {
+            let entry = MirrorEntry {
+                idx,
+                title: format!("strip {idx}"),
+                page: Some(format!("https://example.com/{idx}")),
+                image: format!("https://example.com/{idx}.png"),
+                file: format!("{idx:05}.png"),
+                sha256: String::new(),
+                alt_text: None,
+                hover_text: (idx == 3).then(|| "joke".to_owned()),
+                published: NaiveDate::from_ymd_opt(2024, 1, 1 + idx as u32 * 7),
+                extra_files: Vec::new(),
+                extra_sha256: Vec::new(),
+            };
+            if idx != 4 {
+                std::fs::write(dir.join(&entry.file), &image).unwrap();
+            }
+            manifest += &(serde_json::to_string(&entry).unwrap() + "\n");
+        }
+        std::fs::write(dir.join(MANIFEST), manifest).unwrap();
+
+        let fetcher = build_local_fetcher(Sites::Xkcd, &dir).await.unwrap();
+        let last = fetcher.last().await.unwrap();
+        assert_eq!((last.title.as_str(), last.idx), ("strip 3", 3));
+        assert_eq!(fetcher.strip_count(), Some(4));
+        assert_eq!(last.hover_text.as_deref(), Some("joke"));
+        assert!(last.is_last());
+        let prev = fetcher.prev(last.idx).await.unwrap();
+        assert_eq!(prev.idx, 1);
+        assert_eq!(fetcher.next(prev.idx).await.unwrap().idx, 3);
+        let first = fetcher.prev(prev.idx).await.unwrap();
+        assert!(!first.has_prev());
+        assert!(fetcher.prev(first.idx).await.is_err());
+        assert!(fetcher.next(last.idx).await.is_err());
+        let missing = fetcher.get(2).await.unwrap_err();
+        assert_eq!(FetcherErrors::from(missing), FetcherErrors::OutOfRange);
+        // The 15th belongs to the missing strip, the 22nd to the one without an image
+        let date = |day| NaiveDate::from_ymd_opt(2024, 1, day).unwrap();
+        assert_eq!(fetcher.by_date(date(14)).await.unwrap().idx, 1);
+        assert_eq!(fetcher.by_date(date(31)).await.unwrap().idx, 3);
+        assert_eq!(
+            fetcher.page_url(1).as_deref(),
+            Some("https://example.com/1")
+        );
+
+        let cache = ImageCache::new(200, None).with_local_dir(dir.clone());
+        assert_eq!(cache.get(&last.url).await.unwrap().as_ref(), &image[..]);
+
+        let _ = std::fs::remove_dir_all(&dir);
+        assert!(build_local_fetcher(Sites::Xkcd, &dir).await.is_none());
+    }
+}
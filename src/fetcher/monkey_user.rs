@@ -1,32 +1,34 @@
 use anyhow::{Result, bail};
-use rss::Channel;
 
-use crate::{FetcherErrors, Strip, StripType, Url};
+use crate::{FetcherErrors, Strip, StripType};
 
 use super::FetcherImpl;
 
 impl FetcherImpl {
     pub(super) async fn reload_monkey_user(&mut self) -> Result<()> {
-        let data = reqwest::get(self.site.fetch_url()).await?.bytes().await?;
-        let data: Vec<_> = Channel::read_from(&data[..])?
+        let data = self.index_bytes(self.fetch_url()).await?;
+        let data: Vec<_> = Self::read_feed(&data)?
             .items
             .into_iter()
-            .map(|item| (item.title, item.link))
-            .filter(|(title, link)| {
+            .map(|item| (Self::rss_date(&item), item.title, item.link))
+            .filter(|(_, title, link)| {
                 title.as_ref().is_some_and(|title| !title.is_empty())
                     && link.as_ref().is_some_and(|link| !link.is_empty())
             })
             .enumerate()
-            .map(|(idx, (name, link))| Strip {
-                title: name.unwrap(),
-                url: link.unwrap(),
-                idx,
-                strip_type: StripType::Unknown,
-                site: self.site,
+            .map(|(idx, (published, name, link))| {
+                Strip::new(
+                    self.site,
+                    name.unwrap(),
+                    link.unwrap(),
+                    idx,
+                    StripType::Unknown,
+                )
+                .with_published(published)
             })
             .collect();
         match data.len() {
-            0 => bail!(FetcherErrors::Error404),
+            0 => bail!(FetcherErrors::EmptyArchive),
             _ => {
                 self.posts = Some(data);
                 Ok(())
@@ -35,16 +37,17 @@ impl FetcherImpl {
     }
 
     pub(super) async fn parse_monkey_user_content(&self, content: &Strip) -> Result<Strip> {
-        let data = reqwest::get(&content.url).await?.text().await?;
+        let data = self.http.text(&content.url).await?;
         let url = Self::parse_first_occurrence_blocking(&data, "p img", "src")
-            .ok_or(FetcherErrors::Error404)?;
+            .ok_or_else(|| FetcherErrors::layout_changed("p img"))?;
 
-        Ok(Strip {
-            title: content.title.to_string(),
-            url: "https://".to_string() + self.site.homepage() + &url,
-            idx: content.idx,
-            strip_type: content.strip_type,
-            site: content.site,
-        })
+        Ok(Strip::new(
+            content.site,
+            content.title.to_string(),
+            self.homepage_url() + &url,
+            content.idx,
+            content.strip_type,
+        )
+        .with_published(content.published))
     }
 }
@@ -1,41 +1,41 @@
 use anyhow::{Result, bail};
-use rss::Channel;
 
-use crate::{FetcherErrors, Strip, StripType, Url};
+use crate::{FetcherErrors, Strip, StripType};
 
 use super::FetcherImpl;
 
+/// Pages of a story followed at most, as a guard against link loops
+const OGLAF_MAX_STORY_PAGES: usize = 32;
+
 impl FetcherImpl {
     pub(super) async fn reload_oglaf(&mut self) -> Result<()> {
-        let data = reqwest::get(self.site.fetch_url()).await?.bytes().await?;
-        let data: Vec<_> = Channel::read_from(&data[..])?
+        let data = self.index_bytes(self.fetch_url()).await?;
+        let data: Vec<_> = Self::read_feed(&data)?
             .items
             .into_iter()
-            .map(|item| (item.title, item.description))
-            .filter(|(title, description)| {
+            .map(|item| (Self::rss_date(&item), item.title, item.description))
+            .filter(|(_, title, description)| {
                 title.as_ref().is_some_and(|title| !title.is_empty())
                     && description
                         .as_ref()
                         .is_some_and(|description| !description.is_empty())
             })
-            .map(|(name, description)| {
+            .map(|(published, name, description)| {
                 (
+                    published,
                     name.unwrap(),
                     Self::parse_first_occurrence_blocking(&description.unwrap(), "p a", "href"),
                 )
             })
-            .filter(|(_, url)| url.is_some())
+            .filter(|(_, _, url)| url.is_some())
             .enumerate()
-            .map(|(idx, (title, url))| Strip {
-                title,
-                url: url.unwrap(),
-                idx,
-                strip_type: StripType::Unknown,
-                site: self.site,
+            .map(|(idx, (published, title, url))| {
+                Strip::new(self.site, title, url.unwrap(), idx, StripType::Unknown)
+                    .with_published(published)
             })
             .collect();
         match data.len() {
-            0 => bail!(FetcherErrors::Error404),
+            0 => bail!(FetcherErrors::EmptyArchive),
             _ => {
                 self.posts = Some(data);
                 Ok(())
@@ -44,16 +44,51 @@ impl FetcherImpl {
     }
 
     pub(super) async fn parse_oglaf_content(&self, content: &Strip) -> Result<Strip> {
-        let data = reqwest::get(&content.url).await?.text().await?;
+        let data = self.http.text(&content.url).await?;
         let url = Self::parse_first_occurrence_blocking(&data, "#strip", "src")
-            .ok_or(FetcherErrors::Error404)?;
+            .ok_or_else(|| FetcherErrors::layout_changed("#strip"))?;
+        let (alt_text, hover_text) = Self::parse_image_texts_blocking(&data, "#strip");
+        let extra_urls = self.oglaf_story_pages(&content.url, data).await;
 
-        Ok(Strip {
-            title: content.title.to_string(),
+        Ok(Strip::new(
+            content.site,
+            content.title.to_string(),
             url,
-            idx: content.idx,
-            strip_type: content.strip_type,
-            site: content.site,
-        })
+            content.idx,
+            content.strip_type,
+        )
+        .with_alt_text(alt_text)
+        .with_hover_text(hover_text)
+        .with_published(content.published)
+        .with_extra_urls(extra_urls))
+    }
+
+    /// Images of the pages following `url` in a story spanning several pages, `/story/2/` and so on.
+    ///
+    /// Pages that fail to load end the story early rather than the whole strip.
+    async fn oglaf_story_pages(&self, url: &str, mut data: String) -> Vec<String> {
+        let url = url.trim_end_matches('/');
+        let path = url
+            .split_once("://")
+            .and_then(|(_, rest)| rest.find('/').map(|pos| &rest[pos..]))
+            .unwrap_or_default();
+
+        let mut images = Vec::new();
+        for page in 2..=OGLAF_MAX_STORY_PAGES {
+            let next = format!("{path}/{page}/");
+            let links = Self::parse_all_occurrences_blocking(&data, "a", "href");
+            if !links.iter().any(|link| link.ends_with(&next)) {
+                break;
+            }
+            let Ok(text) = self.http.text(format!("{url}/{page}/")).await else {
+                break;
+            };
+            data = text;
+            match Self::parse_first_occurrence_blocking(&data, "#strip", "src") {
+                Some(image) => images.push(image),
+                None => break,
+            }
+        }
+        images
     }
 }
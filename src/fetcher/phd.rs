@@ -2,14 +2,14 @@ use anyhow::{Result, bail};
 use scraper::{Element, Html, Selector};
 
 use super::FetcherImpl;
-use crate::FetcherErrors::Error404;
-use crate::{FetcherErrors, Strip, StripType, Url};
+use crate::FetcherErrors::Parse;
+use crate::{FetcherErrors, Strip, StripType};
 
 impl FetcherImpl {
     pub(super) async fn reload_phd(&mut self) -> Result<()> {
-        let data = reqwest::get(self.site.fetch_url()).await?.text().await?;
+        let data = self.index_text(self.fetch_url()).await?;
         let frag = Html::parse_document(&data);
-        let selector = Selector::parse("td font a").map_err(|_| Error404)?;
+        let selector = Selector::parse("td font a").map_err(|err| Parse(err.to_string()))?;
         let mut data: Vec<_> = frag
             .select(&selector)
             .enumerate()
@@ -27,19 +27,13 @@ impl FetcherImpl {
                     .inner_html();
                 let url = elem.attr("href").unwrap().to_owned();
 
-                Strip {
-                    title,
-                    url,
-                    idx,
-                    strip_type: StripType::Unknown,
-                    site: self.site,
-                }
+                Strip::new(self.site, title, url, idx, StripType::Unknown)
             })
             .collect();
 
         Self::reverse_strip_vec(&mut data);
         match data.len() {
-            0 => bail!(FetcherErrors::Error404),
+            0 => bail!(FetcherErrors::EmptyArchive),
             _ => {
                 self.posts = Some(data);
                 Ok(())
@@ -48,16 +42,17 @@ impl FetcherImpl {
     }
 
     pub(super) async fn parse_phd_content(&self, content: &Strip) -> Result<Strip> {
-        let data = reqwest::get(&content.url).await?.text().await?;
+        let data = self.http.text(&content.url).await?;
         let url = Self::parse_first_occurrence_blocking(&data, "#comic2", "src")
-            .ok_or(FetcherErrors::Error404)?;
+            .ok_or_else(|| FetcherErrors::layout_changed("#comic2"))?;
 
-        Ok(Strip {
-            title: content.title.clone(),
+        Ok(Strip::new(
+            content.site,
+            content.title.clone(),
             url,
-            idx: content.idx,
-            strip_type: content.strip_type,
-            site: content.site,
-        })
+            content.idx,
+            content.strip_type,
+        )
+        .with_published(content.published))
     }
 }
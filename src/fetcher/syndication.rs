@@ -0,0 +1,91 @@
+use anyhow::Result;
+use atom_syndication::{Entry, Feed, Link};
+use rss::{Channel, Enclosure, Item, extension::Extension};
+
+use super::FetcherImpl;
+
+impl FetcherImpl {
+    /// Channel of the RSS or Atom feed in `data`, told apart by their root element.
+    ///
+    /// Atom feeds are converted so that fetchers only deal with RSS items: entries keep their
+    /// alternate link, the summary (or else the content) as description, image enclosures and
+    /// extensions such as `media:content`.
+    pub(super) fn read_feed(data: &[u8]) -> Result<Channel> {
+        match Channel::read_from(data) {
+            Err(rss::Error::InvalidStartTag) => Ok(atom_channel(Feed::read_from(data)?)),
+            channel => Ok(channel?),
+        }
+    }
+}
+
+fn atom_channel(feed: Feed) -> Channel {
+    Channel {
+        title: feed.title.value,
+        link: alternate_link(&feed.links).unwrap_or_default(),
+        description: feed.subtitle.map(|text| text.value).unwrap_or_default(),
+        items: feed.entries.into_iter().map(atom_item).collect(),
+        ..Default::default()
+    }
+}
+
+fn atom_item(entry: Entry) -> Item {
+    let content = entry.content.and_then(|content| content.value);
+    let description = entry.summary.map(|text| text.value).or(content.clone());
+    let enclosure = entry
+        .links
+        .iter()
+        .filter(|link| link.rel == "enclosure")
+        .find(|link| {
+            link.mime_type
+                .as_deref()
+                .is_some_and(|mime_type| mime_type.starts_with("image/"))
+        })
+        .map(|link| Enclosure {
+            url: link.href.clone(),
+            length: link.length.clone().unwrap_or_default(),
+            mime_type: link.mime_type.clone().unwrap_or_default(),
+        });
+    let extensions = entry
+        .extensions
+        .into_iter()
+        .map(|(prefix, elements)| {
+            let elements = elements
+                .into_iter()
+                .map(|(name, values)| (name, values.into_iter().map(extension).collect()))
+                .collect();
+            (prefix, elements)
+        })
+        .collect();
+
+    Item {
+        title: Some(entry.title.value),
+        link: alternate_link(&entry.links),
+        description,
+        content,
+        enclosure,
+        pub_date: Some(entry.published.unwrap_or(entry.updated).to_rfc2822()),
+        extensions,
+        ..Default::default()
+    }
+}
+
+/// Link to the page itself, `rel` defaulting to `alternate` in Atom
+fn alternate_link(links: &[Link]) -> Option<String> {
+    links
+        .iter()
+        .find(|link| link.rel == "alternate")
+        .map(|link| link.href.clone())
+}
+
+fn extension(extension: atom_syndication::extension::Extension) -> Extension {
+    Extension {
+        name: extension.name,
+        value: extension.value,
+        attrs: extension.attrs,
+        children: extension
+            .children
+            .into_iter()
+            .map(|(name, children)| (name, children.into_iter().map(self::extension).collect()))
+            .collect(),
+    }
+}
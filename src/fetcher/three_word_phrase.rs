@@ -2,14 +2,14 @@ use anyhow::{Result, bail};
 use scraper::{Html, Selector};
 
 use super::FetcherImpl;
-use crate::FetcherErrors::Error404;
-use crate::{FetcherErrors, Strip, StripType, Url};
+use crate::FetcherErrors::Parse;
+use crate::{FetcherErrors, Strip, StripType};
 
 impl FetcherImpl {
     pub(super) async fn reload_three_word_phrase(&mut self) -> Result<()> {
-        let data = reqwest::get(self.site.fetch_url()).await?.text().await?;
+        let data = self.index_text(self.fetch_url()).await?;
         let frag = Html::parse_document(&data);
-        let selector = Selector::parse("span.links a").map_err(|_| Error404)?;
+        let selector = Selector::parse("span.links a").map_err(|err| Parse(err.to_string()))?;
         let data: Vec<_> = frag
             .select(&selector)
             .map(|element| {
@@ -20,20 +20,18 @@ impl FetcherImpl {
             })
             .filter(|(title, url)| !title.is_empty() && url.is_some())
             .enumerate()
-            .map(|(idx, (title, url))| Strip {
-                title,
-                url: format!(
-                    "https://{}/{}",
-                    self.site.homepage(),
-                    url.unwrap().to_owned()
-                ),
-                idx,
-                strip_type: StripType::Unknown,
-                site: self.site,
+            .map(|(idx, (title, url))| {
+                Strip::new(
+                    self.site,
+                    title,
+                    format!("{}/{}", self.homepage_url(), url.unwrap().to_owned()),
+                    idx,
+                    StripType::Unknown,
+                )
             })
             .collect();
         match data.len() {
-            0 => bail!(FetcherErrors::Error404),
+            0 => bail!(FetcherErrors::EmptyArchive),
             _ => {
                 self.posts = Some(data);
                 Ok(())
@@ -42,16 +40,17 @@ impl FetcherImpl {
     }
 
     pub(super) async fn parse_three_word_phrase_content(&self, content: &Strip) -> Result<Strip> {
-        let data = reqwest::get(&content.url).await?.text().await?;
+        let data = self.http.text(&content.url).await?;
         let url = Self::parse_first_occurrence_blocking(&data, "td center img", "src")
-            .ok_or(FetcherErrors::Error404)?
+            .ok_or_else(|| FetcherErrors::layout_changed("td center img"))?
             .replace("..", &content.title);
-        Ok(Strip {
-            title: content.title.clone(),
-            url: format!("https://{}/{}", self.site.homepage(), url),
-            idx: content.idx,
-            strip_type: content.strip_type,
-            site: content.site,
-        })
+        Ok(Strip::new(
+            content.site,
+            content.title.clone(),
+            format!("{}/{}", self.homepage_url(), url),
+            content.idx,
+            content.strip_type,
+        )
+        .with_published(content.published))
     }
 }
@@ -2,17 +2,14 @@ use anyhow::{Result, bail};
 use scraper::{Html, Selector};
 
 use super::FetcherImpl;
-use crate::FetcherErrors::Error404;
-use crate::{FetcherErrors, Strip, StripType, Url};
+use crate::FetcherErrors::Parse;
+use crate::{FetcherErrors, Strip, StripType};
 
 impl FetcherImpl {
     pub(super) async fn reload_turnoff_us(&mut self) -> Result<()> {
-        let data = reqwest::get(self.site.fetch_url().to_owned() + "/all")
-            .await?
-            .text()
-            .await?;
+        let data = self.index_text(self.fetch_url() + "/all").await?;
         let frag = Html::parse_document(&data);
-        let selector = Selector::parse("a.post-link").map_err(|_| Error404)?;
+        let selector = Selector::parse("a.post-link").map_err(|err| Parse(err.to_string()))?;
         let data: Vec<_> = frag
             .select(&selector)
             .map(|element| {
@@ -23,17 +20,19 @@ impl FetcherImpl {
             })
             .filter(|(title, url)| !title.is_empty() && url.is_some())
             .enumerate()
-            .map(|(idx, (title, url))| Strip {
-                title,
-                url: self.site.fetch_url().to_owned() + url.unwrap(),
-                idx,
-                strip_type: StripType::Unknown,
-                site: self.site,
+            .map(|(idx, (title, url))| {
+                Strip::new(
+                    self.site,
+                    title,
+                    self.fetch_url() + url.unwrap(),
+                    idx,
+                    StripType::Unknown,
+                )
             })
             .collect();
 
         match data.len() {
-            0 => bail!(FetcherErrors::Error404),
+            0 => bail!(FetcherErrors::EmptyArchive),
             _ => {
                 self.posts = Some(data);
                 Ok(())
@@ -42,16 +41,17 @@ impl FetcherImpl {
     }
 
     pub(super) async fn parse_turnoff_us_content(&self, content: &Strip) -> Result<Strip> {
-        let data = reqwest::get(&content.url).await?.text().await?;
+        let data = self.http.text(&content.url).await?;
         let url = Self::parse_first_occurrence_blocking(&data, "p img", "src")
-            .ok_or(FetcherErrors::Error404)?;
+            .ok_or_else(|| FetcherErrors::layout_changed("p img"))?;
 
-        Ok(Strip {
-            title: content.title.to_string(),
-            url: self.site.fetch_url().to_owned() + &url,
-            idx: content.idx,
-            strip_type: content.strip_type,
-            site: content.site,
-        })
+        Ok(Strip::new(
+            content.site,
+            content.title.to_string(),
+            self.fetch_url() + &url,
+            content.idx,
+            content.strip_type,
+        )
+        .with_published(content.published))
     }
 }
@@ -0,0 +1,196 @@
+use anyhow::{Result, bail};
+use chrono::NaiveDateTime;
+use reqwest::StatusCode;
+use scraper::Html;
+use serde::Deserialize;
+
+use super::{FetcherImpl, absolute_url};
+use crate::{FetcherErrors, Sites, Strip, StripType};
+
+/// Posts asked for per page, the most the REST API allows
+const WORDPRESS_PER_PAGE: usize = 100;
+
+/// Post as listed by `/wp-json/wp/v2/posts`, or by `/wp-json/wp/v2/comic` for the sites using
+/// Comic Easel
+#[derive(Deserialize)]
+struct Post {
+    link: String,
+    /// Publication date in the timezone of the site, `2024-03-12T10:00:00`
+    date: Option<String>,
+    title: Rendered,
+    content: Option<Rendered>,
+    #[serde(rename = "_embedded")]
+    embedded: Option<Embedded>,
+}
+
+#[derive(Deserialize)]
+struct Rendered {
+    rendered: String,
+}
+
+#[derive(Deserialize)]
+struct Embedded {
+    #[serde(rename = "wp:featuredmedia", default)]
+    featured_media: Vec<Media>,
+}
+
+/// Featured image, its `source_url` is missing when the image can't be shown
+#[derive(Deserialize)]
+struct Media {
+    source_url: Option<String>,
+}
+
+impl FetcherImpl {
+    pub(super) async fn reload_wordpress(&mut self) -> Result<()> {
+        let data = self.wordpress_strips(None).await?;
+        match data.len() {
+            0 => bail!(FetcherErrors::EmptyArchive),
+            _ => {
+                self.posts = Some(data);
+                Ok(())
+            }
+        }
+    }
+
+    pub(super) async fn refresh_wordpress(&self, latest: &Strip) -> Result<Vec<Strip>> {
+        self.wordpress_strips(Some(&latest.url)).await
+    }
+
+    /// Walk the pages of the REST API, newest posts first, stopping after the page that lists
+    /// `known_url`.
+    ///
+    /// Posts keep their link as `url`, the comic found in their content, or else their featured
+    /// image, is kept in `images` until they are resolved.
+    async fn wordpress_strips(&self, known_url: Option<&str>) -> Result<Vec<Strip>> {
+        let (selector, attr) = self.wordpress_layout()?;
+        let mut data = Vec::new();
+        let mut pages = None;
+
+        for page in 1.. {
+            let url = format!(
+                "{}?page={page}&per_page={WORDPRESS_PER_PAGE}&_embed=wp:featuredmedia",
+                self.fetch_url()
+            );
+            // Only the first page tells whether anything changed
+            let res = match page {
+                1 => self.fetch_index(url).await?,
+                _ => match self.http.get(url).await {
+                    // Past the last page, when the site didn't tell how many there are
+                    Err(err) if is_bad_request(&err) => break,
+                    res => res?,
+                },
+            };
+            if page == 1 {
+                pages = res
+                    .headers()
+                    .get("x-wp-totalpages")
+                    .and_then(|pages| pages.to_str().ok()?.parse::<usize>().ok());
+            }
+            let posts: Vec<Post> = serde_json::from_str(&res.text().await?)?;
+            if posts.is_empty() {
+                break;
+            }
+
+            let mut known = false;
+            for post in posts {
+                known |= known_url == Some(post.link.as_str());
+                let title = Html::parse_fragment(&post.title.rendered)
+                    .root_element()
+                    .text()
+                    .collect::<String>()
+                    .trim()
+                    .to_owned();
+                if title.is_empty() || post.link.is_empty() {
+                    continue;
+                }
+                let mut images = post
+                    .content
+                    .map(|content| {
+                        Self::parse_all_occurrences_blocking(&content.rendered, selector, attr)
+                    })
+                    .unwrap_or_default();
+                if images.is_empty() {
+                    images = post
+                        .embedded
+                        .into_iter()
+                        .flat_map(|embedded| embedded.featured_media)
+                        .filter_map(|media| media.source_url)
+                        .collect();
+                }
+                let published = post.date.and_then(|date| {
+                    NaiveDateTime::parse_from_str(&date, "%Y-%m-%dT%H:%M:%S")
+                        .ok()
+                        .map(|date| date.date())
+                });
+                if !images.is_empty() {
+                    let images = images
+                        .iter()
+                        .map(|image| absolute_url(&post.link, image))
+                        .collect();
+                    self.images
+                        .lock()
+                        .unwrap()
+                        .insert(post.link.clone(), images);
+                }
+                data.push(
+                    Strip::new(self.site, title, post.link, 0, StripType::Unknown)
+                        .with_published(published),
+                );
+            }
+            if known || pages.is_some_and(|pages| page >= pages) {
+                break;
+            }
+        }
+        Ok(data)
+    }
+
+    /// Images found through the REST API, or else in the page of the post
+    pub(super) async fn parse_wordpress_content(&self, content: &Strip) -> Result<Strip> {
+        let (selector, attr) = self.wordpress_layout()?;
+        let images = self.images.lock().unwrap().get(&content.url).cloned();
+        let urls = match images {
+            Some(images) => images,
+            None => {
+                let data = self.http.text(&content.url).await?;
+                Self::parse_all_occurrences_blocking(&data, selector, attr)
+                    .iter()
+                    .map(|image| absolute_url(&content.url, image))
+                    .collect()
+            }
+        };
+        let mut urls = urls.into_iter();
+        let url = urls
+            .next()
+            .ok_or_else(|| FetcherErrors::layout_changed(selector))?;
+
+        Ok(Strip::new(
+            content.site,
+            content.title.clone(),
+            url,
+            content.idx,
+            content.strip_type,
+        )
+        .with_published(content.published)
+        .with_extra_urls(urls.collect()))
+    }
+
+    /// Selector and attribute of the comic, in the content of a post as well as in its page
+    fn wordpress_layout(&self) -> Result<(&'static str, &'static str)> {
+        match self.site {
+            Sites::CadComics => Ok((".comicpage a img", "src")),
+            Sites::PoorlyDrawnLines => Ok(("figure.wp-block-image a", "href")),
+            Sites::Custom(site) => {
+                let definition = site.definition().ok_or(FetcherErrors::Unavailable)?;
+                Ok((&definition.image_selector, &definition.image_attr))
+            }
+            _ => bail!(FetcherErrors::Unavailable),
+        }
+    }
+}
+
+/// Answer of the REST API to a page past the last one
+fn is_bad_request(err: &anyhow::Error) -> bool {
+    err.downcast_ref::<reqwest::Error>()
+        .and_then(reqwest::Error::status)
+        == Some(StatusCode::BAD_REQUEST)
+}
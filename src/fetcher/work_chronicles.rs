@@ -2,20 +2,39 @@ use anyhow::{Result, bail};
 use scraper::{Html, Selector};
 
 use super::FetcherImpl;
-use crate::FetcherErrors::Error404;
-use crate::{FetcherErrors, Strip, StripType, Url};
+use crate::FetcherErrors::Parse;
+use crate::{FetcherErrors, Strip, StripType};
 
 impl FetcherImpl {
     pub(super) async fn reload_work_chronicles(&mut self) -> Result<()> {
+        let data = self.work_chronicles_strips(None).await?;
+        match data.len() {
+            0 => bail!(FetcherErrors::EmptyArchive),
+            _ => {
+                self.posts = Some(data);
+                Ok(())
+            }
+        }
+    }
+
+    pub(super) async fn refresh_work_chronicles(&self, latest: &Strip) -> Result<Vec<Strip>> {
+        self.work_chronicles_strips(Some(&latest.url)).await
+    }
+
+    /// Walk the sitemap pages, newest first, stopping after the page that lists `known_url`.
+    ///
+    /// `/sitemap` only links the years, new strips change the page of the newest year, which is
+    /// the one revalidated.
+    async fn work_chronicles_strips(&self, known_url: Option<&str>) -> Result<Vec<Strip>> {
         let mut data = Vec::new();
         let mut counter = 0;
 
-        let selector = Selector::parse("a.sitemap-link").map_err(|_| Error404)?;
+        let selector = Selector::parse("a.sitemap-link").map_err(|err| Parse(err.to_string()))?;
 
         let urls: Vec<_> = {
-            let data = reqwest::get(format!("{}/sitemap", self.site.fetch_url()))
-                .await?
-                .text()
+            let data = self
+                .http
+                .text(format!("{}/sitemap", self.fetch_url()))
                 .await?;
             let frag = Html::parse_document(&data);
             frag.select(&selector)
@@ -23,11 +42,12 @@ impl FetcherImpl {
                 .collect()
         };
 
-        for url in urls.iter() {
-            let html = reqwest::get(format!("{}{url}", self.site.fetch_url()))
-                .await?
-                .text()
-                .await?;
+        for (pos, url) in urls.iter().enumerate() {
+            let url = format!("{}{url}", self.fetch_url());
+            let html = match pos {
+                0 => self.index_text(url).await?,
+                _ => self.http.text(url).await?,
+            };
             let frag = Html::parse_document(&html);
             for elem in frag.select(&selector) {
                 let inner_html = elem.inner_html();
@@ -35,38 +55,42 @@ impl FetcherImpl {
 
                 if let Some((_, title)) = title_split {
                     let url = elem.value().attr("href").unwrap().to_owned();
-                    data.push(Strip {
-                        title: title.to_owned(),
+                    data.push(Strip::new(
+                        self.site,
+                        title.to_owned(),
                         url,
-                        idx: counter,
-                        strip_type: StripType::Unknown,
-                        site: self.site,
-                    });
+                        counter,
+                        StripType::Unknown,
+                    ));
                     counter += 1;
                 }
             }
-        }
 
-        match data.len() {
-            0 => bail!(FetcherErrors::Error404),
-            _ => {
-                self.posts = Some(data);
-                Ok(())
+            if known_url.is_some_and(|known_url| data.iter().any(|strip| strip.url == known_url)) {
+                break;
             }
         }
+
+        Ok(data)
     }
 
     pub(super) async fn parse_work_chronicles_content(&self, content: &Strip) -> Result<Strip> {
-        let data = reqwest::get(&content.url).await?.text().await?;
-        let url = Self::parse_first_occurrence_blocking(&data, "figure a.image-link", "href")
-            .ok_or(FetcherErrors::Error404)?;
+        let data = self.http.text(&content.url).await?;
+        // Some posts are made of several figures
+        let mut urls =
+            Self::parse_all_occurrences_blocking(&data, "figure a.image-link", "href").into_iter();
+        let url = urls
+            .next()
+            .ok_or_else(|| FetcherErrors::layout_changed("figure a.image-link"))?;
 
-        Ok(Strip {
-            title: content.title.clone(),
+        Ok(Strip::new(
+            content.site,
+            content.title.clone(),
             url,
-            idx: content.idx,
-            strip_type: content.strip_type,
-            site: content.site,
-        })
+            content.idx,
+            content.strip_type,
+        )
+        .with_published(content.published)
+        .with_extra_urls(urls.collect()))
     }
 }
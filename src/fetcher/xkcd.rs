@@ -1,43 +1,58 @@
 use anyhow::Result;
 
-use crate::{FetcherErrors, Strip, StripType, Url};
+use crate::{FetcherErrors, Strip, StripType};
 
 use super::FetcherImpl;
 
 impl FetcherImpl {
     pub(super) async fn reload_xkcd(&mut self) -> Result<()> {
-        let data = reqwest::get(self.site.fetch_url()).await?.text().await?;
+        let data = self.xkcd_strips_after(0).await?;
+        self.posts = Some(data);
+        Ok(())
+    }
+
+    pub(super) async fn refresh_xkcd(&self, latest: &Strip) -> Result<Vec<Strip>> {
+        let known = latest.title.parse::<usize>()?;
+        self.xkcd_strips_after(known).await
+    }
+
+    /// Strips numbered after `known`, newest first
+    async fn xkcd_strips_after(&self, known: usize) -> Result<Vec<Strip>> {
+        let data = self.index_text(self.fetch_url()).await?;
         let last = self
             .parse_meta_content_blocking(data, "og:url")
-            .ok_or(FetcherErrors::Error404)?
+            .ok_or_else(|| FetcherErrors::layout_changed("og:url"))?
             .replace('/', "")
             .parse::<usize>()?;
         let mut data = Vec::new();
-        for idx in (1..last + 1).rev() {
-            data.push(Strip {
-                title: idx.to_string(),
-                url: self.site.fetch_url().to_owned() + "/" + &idx.to_string(),
-                idx: last - idx,
-                strip_type: StripType::Unknown,
-                site: self.site,
-            })
+        for idx in (known + 1..last + 1).rev() {
+            data.push(Strip::new(
+                self.site,
+                idx.to_string(),
+                self.fetch_url() + "/" + &idx.to_string(),
+                idx - 1,
+                StripType::Unknown,
+            ))
         }
-        self.posts = Some(data);
-        Ok(())
+        Ok(data)
     }
 
     pub(super) async fn parse_xkcd_content(&self, content: &Strip) -> Result<Strip> {
-        let data = reqwest::get(&content.url).await?.text().await?;
+        let data = self.http.text(&content.url).await?;
+        let (alt_text, hover_text) = Self::parse_image_texts_blocking(&data, "#comic img");
         let url = self
             .parse_meta_content_blocking(data, "og:image")
-            .ok_or(FetcherErrors::Error404)?;
+            .ok_or_else(|| FetcherErrors::layout_changed("og:image"))?;
 
-        Ok(Strip {
-            title: content.title.to_string(),
+        Ok(Strip::new(
+            content.site,
+            content.title.to_string(),
             url,
-            idx: content.idx,
-            strip_type: content.strip_type,
-            site: content.site,
-        })
+            content.idx,
+            content.strip_type,
+        )
+        .with_alt_text(alt_text)
+        .with_hover_text(hover_text)
+        .with_published(content.published))
     }
 }
@@ -1,5 +1,11 @@
+use std::collections::{HashMap, HashSet};
+
 use anyhow::{Result, anyhow};
-use eframe::egui::{CentralPanel, ComboBox, Label, Layout, Panel, ViewportBuilder};
+use chrono::NaiveDate;
+use eframe::egui::{
+    Button, CentralPanel, ComboBox, Image, ImageSource, Label, Layout, Panel, ScrollArea, TextEdit,
+    ViewportBuilder,
+};
 use egui_file_dialog::FileDialog;
 use egui_theme_switcher::theme_switcher;
 use tokio::{
@@ -8,8 +14,11 @@ use tokio::{
 };
 
 use crate::{
-    Sites, Strip, Url,
+    FetcherErrors, Sites, Strip, Url,
     backend::{Request, RequestStripType, Response},
+    cache::ImageData,
+    mirror::has_mirror,
+    settings::config_errors,
 };
 
 use super::Runnable;
@@ -27,7 +36,18 @@ impl Runnable for EguiFrontend {
         let app = App {
             mode: RequestStripType::Last,
             source: Sites::default(),
+            offline: HashSet::new(),
             strip: None,
+            error: None,
+            image: None,
+            extra_images: HashMap::new(),
+            date: String::new(),
+            feed_url: String::new(),
+            subscribing: false,
+            subscribe_error: None,
+            downloading: false,
+            download_error: None,
+            config_errors: config_errors(),
             tx,
             rx,
             file_dialog: Some(FileDialog::new()),
@@ -49,7 +69,26 @@ struct App {
     file_dialog: Option<FileDialog>,
     mode: RequestStripType,
     source: Sites,
+    /// Sites browsed from their mirror
+    offline: HashSet<Sites>,
     strip: Option<Option<Strip>>,
+    /// Why the last strip request failed
+    error: Option<FetcherErrors>,
+    image: Option<Option<ImageData>>,
+    /// Other images of the strip, by url, once loaded
+    extra_images: HashMap<String, Option<ImageData>>,
+    /// Date of the strip to go to, as `YYYY-MM-DD`
+    date: String,
+    /// Url of the feed to subscribe to
+    feed_url: String,
+    /// Waiting for the feed to be discovered
+    subscribing: bool,
+    subscribe_error: Option<FetcherErrors>,
+    /// Waiting for the image to be saved
+    downloading: bool,
+    download_error: Option<anyhow::Error>,
+    /// Broken configuration files, shown until dismissed
+    config_errors: Vec<String>,
     tx: Sender<Request>,
     rx: Receiver<Response>,
 }
@@ -57,10 +96,14 @@ struct App {
 impl App {
     fn force_refresh(&mut self, mode: RequestStripType) {
         self.strip = None;
+        self.error = None;
+        self.image = None;
+        self.extra_images.clear();
         self.mode = mode;
     }
 
     fn get_content(&mut self) -> &Option<Strip> {
+        self.poll_responses();
         match self.strip {
             None => {
                 let req = Request::Strip {
@@ -72,42 +115,127 @@ impl App {
                 }
                 &None
             }
+            Some(ref val) => val,
+        }
+    }
+
+    /// First image of the strip, a spinner while it loads or the failure with a way to retry
+    fn show_image(&mut self, ui: &mut eframe::egui::Ui) -> eframe::egui::Response {
+        let url = self
+            .get_content()
+            .as_ref()
+            .map(|content| content.url.clone());
+        if let Some(err) = self.error.as_ref() {
+            let retry = ui.vertical_centered(|ui| {
+                ui.label(format!("Failed to load the strip: {err}"));
+                ui.button("Retry").clicked()
+            });
+            if retry.inner {
+                self.force_refresh(self.mode);
+            }
+            return retry.response;
+        }
+        match (url, self.image.as_ref()) {
+            (None, _) | (Some(_), None) => ui.spinner(),
+            (Some(url), Some(Some(data))) => ui.image(ImageSource::Bytes {
+                uri: format!("bytes://{url}").into(),
+                bytes: data.clone().into(),
+            }),
+            // Let egui loaders try on their own
+            (Some(url), Some(None)) => ui.image(url),
+        }
+    }
+
+    /// Another image of the strip, a spinner while it loads
+    fn show_extra_image(&self, ui: &mut eframe::egui::Ui, url: &str) -> eframe::egui::Response {
+        let max_width = ui.available_width();
+        match self.extra_images.get(url) {
+            None => ui.spinner(),
+            Some(Some(data)) => ui.add(
+                Image::new(ImageSource::Bytes {
+                    uri: format!("bytes://{url}").into(),
+                    bytes: data.clone().into(),
+                })
+                .max_width(max_width),
+            ),
+            // Let egui loaders try on their own
+            Some(None) => ui.add(Image::new(url).max_width(max_width)),
+        }
+    }
 
-            Some(None) => match self.rx.try_recv() {
-                Ok(Response::Strip(data)) => {
-                    if data.as_ref().is_some_and(|data| data.site == self.source) {
-                        self.strip = Some(data);
-                        self.strip.as_ref().unwrap()
-                    } else {
-                        &None
+    /// Handle the responses arrived since the last frame, those of older requests are dropped
+    fn poll_responses(&mut self) {
+        while let Ok(res) = self.rx.try_recv() {
+            match res {
+                // Only the answer to the request pending, `source` and `mode`, is kept
+                Response::Strip { site, ty, strip }
+                    if matches!(self.strip, Some(None))
+                        && (site, ty) == (self.source, self.mode) =>
+                {
+                    match strip {
+                        Ok(strip) => {
+                            for url in strip.image_urls() {
+                                let url = url.to_owned();
+                                let _ = self.tx.blocking_send(Request::Image { url });
+                            }
+                            self.strip = Some(Some(strip));
+                        }
+                        Err(err) => self.error = Some(err),
                     }
                 }
-                _ => &None,
-            },
-            Some(ref val) => val,
+                Response::Image { url, data } => {
+                    let Some(Some(strip)) = self.strip.as_ref() else {
+                        continue;
+                    };
+                    if url == strip.url {
+                        if self.image.is_none() {
+                            self.image = Some(data.ok());
+                        }
+                    } else if strip.extra_urls.contains(&url) {
+                        self.extra_images.entry(url).or_insert(data.ok());
+                    }
+                }
+                Response::Download(res) => {
+                    self.downloading = false;
+                    self.download_error = res.err();
+                }
+                Response::Subscribed(res) => {
+                    self.subscribing = false;
+                    match res {
+                        Ok(site) => {
+                            self.source = site;
+                            self.feed_url.clear();
+                            self.subscribe_error = None;
+                            self.force_refresh(RequestStripType::Last);
+                        }
+                        Err(err) => self.subscribe_error = Some(err),
+                    }
+                }
+                _ => {}
+            }
         }
     }
 
-    fn maybe_download_content(
-        &mut self,
-        url: String,
-        ctx: &eframe::egui::Context,
-    ) -> Option<Result<()>> {
-        if let Some(file_dialog) = self.file_dialog.as_mut() {
-            if let Some(path) = file_dialog.update(ctx).picked() {
-                let path = path.to_path_buf();
+    /// Follow the feed typed in, switching to it once `Subscribed` comes back
+    fn subscribe(&mut self) {
+        let url = self.feed_url.trim().to_owned();
+        self.subscribing = self.tx.blocking_send(Request::Subscribe { url }).is_ok();
+    }
 
-                self.file_dialog = None;
+    /// Save the image once a file is picked, `Download` tells how it went
+    fn maybe_download_content(&mut self, url: String, ctx: &eframe::egui::Context) {
+        if let Some(file_dialog) = self.file_dialog.as_mut()
+            && let Some(path) = file_dialog.update(ctx).picked()
+        {
+            let path = path.to_path_buf();
 
-                let _ = self.tx.blocking_send(Request::Download { path, url });
-                return if let Some(Response::Download(res)) = self.rx.blocking_recv() {
-                    Some(res)
-                } else {
-                    None
-                };
-            }
+            self.file_dialog = None;
+
+            self.downloading = self
+                .tx
+                .blocking_send(Request::Download { path, url })
+                .is_ok();
         }
-        None
     }
 
     fn open_file_dialog(&mut self, file_name: String) {
@@ -126,10 +254,20 @@ impl App {
 impl eframe::App for App {
     fn ui(&mut self, ui: &mut eframe::egui::Ui, _frame: &mut eframe::Frame) {
         let sites: Vec<_> = Sites::sites_sorted();
+        if !self.config_errors.is_empty() {
+            Panel::top("config_errors_panel").show(ui, |ui| {
+                ui.horizontal(|ui| {
+                    ui.label(format!("Ignoring {}", self.config_errors.join(", ")));
+                    if ui.button("Dismiss").clicked() {
+                        self.config_errors.clear();
+                    }
+                });
+            });
+        }
         Panel::bottom("my_panel").show(ui, |ui| {
             ui.horizontal(|ui| {
                 ComboBox::from_label("")
-                    .selected_text(format!("{:?}", self.source))
+                    .selected_text(self.source.to_string())
                     .show_ui(ui, |ui| {
                         for site in sites.into_iter() {
                             if ui
@@ -144,6 +282,48 @@ impl eframe::App for App {
                 let homepage = self.source.homepage();
                 ui.hyperlink_to(homepage, "https://".to_owned() + homepage);
 
+                let mut offline = self.offline.contains(&self.source);
+                ui.add_enabled_ui(offline || has_mirror(self.source), |ui| {
+                    if ui
+                        .checkbox(&mut offline, "Offline")
+                        .on_disabled_hover_text("Mirror the site to read it offline")
+                        .changed()
+                    {
+                        if offline {
+                            self.offline.insert(self.source);
+                        } else {
+                            self.offline.remove(&self.source);
+                        }
+                        let req = Request::Offline {
+                            site: self.source,
+                            enabled: offline,
+                        };
+                        let _ = self.tx.blocking_send(req);
+                        self.force_refresh(RequestStripType::Last);
+                    }
+                });
+
+                ui.separator();
+
+                let feed_url = TextEdit::singleline(&mut self.feed_url)
+                    .hint_text("Feed url")
+                    .desired_width(160.0);
+                ui.add(feed_url);
+                let subscribe = ui.add_enabled(
+                    !self.subscribing && !self.feed_url.trim().is_empty(),
+                    Button::new("Subscribe"),
+                );
+                let subscribe = match self.subscribe_error.as_ref() {
+                    Some(err) => subscribe.on_hover_text(format!("Subscription failed: {err}")),
+                    None => subscribe.on_hover_text("Follow the RSS or Atom feed of a webcomic"),
+                };
+                if subscribe.clicked() {
+                    self.subscribe();
+                }
+                if self.subscribing {
+                    ui.spinner();
+                }
+
                 ui.separator();
 
                 let (prev_available, next_available) = {
@@ -182,6 +362,20 @@ impl eframe::App for App {
                     self.force_refresh(RequestStripType::Random)
                 }
 
+                let date = TextEdit::singleline(&mut self.date)
+                    .hint_text("YYYY-MM-DD")
+                    .desired_width(80.0);
+                ui.add(date);
+                let date = NaiveDate::parse_from_str(self.date.trim(), "%Y-%m-%d").ok();
+                if ui
+                    .add_enabled(date.is_some(), Button::new("Go to date"))
+                    .on_hover_text("Strip published closest to the date")
+                    .clicked()
+                    && let Some(date) = date
+                {
+                    self.force_refresh(RequestStripType::ByDate(date))
+                }
+
                 ui.with_layout(Layout::right_to_left(eframe::egui::Align::Center), |ui| {
                     ui.add(theme_switcher());
                     ui.separator();
@@ -191,9 +385,17 @@ impl eframe::App for App {
                         .as_ref()
                         .map(|strip| (strip.title.clone(), strip.url.clone(), strip.file_name()))
                     {
-                        if ui.button("Download").clicked() {
+                        let download = ui.add_enabled(!self.downloading, Button::new("Download"));
+                        let download = match self.download_error.as_ref() {
+                            Some(err) => download.on_hover_text(format!("Download failed: {err}")),
+                            None => download,
+                        };
+                        if download.clicked() {
                             self.open_file_dialog(file_name);
                         }
+                        if self.downloading {
+                            ui.spinner();
+                        }
 
                         ui.add(Label::new(&title).truncate());
 
@@ -203,14 +405,45 @@ impl eframe::App for App {
             });
         });
 
+        let (alt_text, hover_text) = self
+            .get_content()
+            .as_ref()
+            .map(|strip| (strip.alt_text.clone(), strip.hover_text.clone()))
+            .unwrap_or_default();
+        if let Some(alt_text) = alt_text {
+            Panel::bottom("caption_panel").show(ui, |ui| {
+                ui.vertical_centered(|ui| ui.add(Label::new(alt_text).wrap()));
+            });
+        }
+
+        let extra_urls = self
+            .get_content()
+            .as_ref()
+            .map(|strip| strip.extra_urls.clone())
+            .unwrap_or_default();
         CentralPanel::default().show(ui, |ui| {
-            ui.with_layout(
-                Layout::centered_and_justified(eframe::egui::Direction::LeftToRight),
-                |ui| match self.get_content() {
-                    None => ui.spinner(),
-                    Some(content) => ui.image(&content.url),
-                },
-            )
+            let mut images = Vec::with_capacity(extra_urls.len() + 1);
+            if extra_urls.is_empty() {
+                ui.with_layout(
+                    Layout::centered_and_justified(eframe::egui::Direction::LeftToRight),
+                    |ui| images.push(self.show_image(ui)),
+                );
+            } else {
+                // Strips made of several images are stacked, scrolling down
+                ScrollArea::vertical().show(ui, |ui| {
+                    ui.vertical_centered(|ui| {
+                        images.push(self.show_image(ui));
+                        for url in extra_urls {
+                            images.push(self.show_extra_image(ui, &url));
+                        }
+                    });
+                });
+            }
+            if let Some(hover_text) = hover_text {
+                for image in images {
+                    image.on_hover_text(hover_text.as_str());
+                }
+            }
         });
     }
 }
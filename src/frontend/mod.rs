@@ -12,6 +12,9 @@ pub mod egui;
 #[cfg(feature = "slint_frontend")]
 pub mod slint;
 
+#[cfg(feature = "tui_frontend")]
+pub mod tui;
+
 pub trait Runnable {
     fn run(handle: Handle, tx: Sender<Request>, rx: Receiver<Response>) -> Result<()>;
 }
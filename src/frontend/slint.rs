@@ -2,18 +2,21 @@
 #![cfg_attr(not(debug_assertions), windows_subsystem = "windows")]
 
 use anyhow::Result;
+use chrono::NaiveDate;
 use image::ImageReader;
 use native_dialog::DialogBuilder;
 use slint::{ComponentHandle, Image, ModelRc, Rgba8Pixel, SharedPixelBuffer, SharedString, Weak};
-use std::{io::Cursor, str::FromStr};
+use std::{cell::RefCell, collections::HashSet, io::Cursor, rc::Rc, str::FromStr};
 use tokio::{
     runtime::Handle,
     sync::mpsc::{Receiver, Sender},
 };
 
 use crate::{
-    Sites, Url,
+    Sites, Strip, Url,
     backend::{Request, RequestStripType, Response},
+    mirror::has_mirror,
+    settings::config_errors,
 };
 
 use super::Runnable;
@@ -28,21 +31,47 @@ impl Runnable for SlintFrontend {
         let ui = AppWindow::new()?;
         // Setup ComboBox
         ui.set_sites(sites_to_model());
+        ui.set_config_errors(SharedString::from(config_errors().join(", ")));
 
         let listener_ui_weak = ui.as_weak();
         let selected_ui_weak = ui.as_weak();
         let last_ui_weak = ui.as_weak();
         let random_ui_weak = ui.as_weak();
+        let date_ui_weak = ui.as_weak();
         let prev_ui_weak = ui.as_weak();
         let next_ui_weak = ui.as_weak();
         let download_ui_weak = ui.as_weak();
+        let offline_ui_weak = ui.as_weak();
+        let page_ui_weak = ui.as_weak();
+        let retry_ui_weak = ui.as_weak();
+        let subscribe_ui_weak = ui.as_weak();
 
         let selected_tx = tx.clone();
         let next_tx = tx.clone();
         let prev_tx = tx.clone();
         let last_tx = tx.clone();
         let random_tx = tx.clone();
+        let date_tx = tx.clone();
         let download_tx = tx.clone();
+        let offline_tx = tx.clone();
+        let page_tx = tx.clone();
+        let retry_tx = tx.clone();
+        let subscribe_tx = tx.clone();
+        let listener_tx = tx.clone();
+
+        // Last strip asked for, sent again on retry
+        let sent: Sent = Rc::default();
+        let selected_sent = sent.clone();
+        let offline_sent = sent.clone();
+        let last_sent = sent.clone();
+        let random_sent = sent.clone();
+        let date_sent = sent.clone();
+        let prev_sent = sent.clone();
+        let next_sent = sent.clone();
+
+        // Sites browsed from their mirror
+        let offline_sites: Rc<RefCell<HashSet<Sites>>> = Rc::default();
+        let selected_offline_sites = offline_sites.clone();
 
         ui.on_site_selected(move |site: SharedString| {
             // Will never explode. ComboBox values are derived by site.display()
@@ -50,9 +79,53 @@ impl Runnable for SlintFrontend {
             let ui = selected_ui_weak.unwrap();
 
             ui.set_url_site(SharedString::from(site.homepage()));
+            ui.set_offline(selected_offline_sites.borrow().contains(&site));
+            ui.set_offline_available(has_mirror(site));
             reset_strip(&ui);
 
-            last(&selected_tx, site);
+            last(&selected_tx, &selected_sent, site);
+        });
+
+        ui.on_offline_toggled(move |site, enabled| {
+            let ui = offline_ui_weak.unwrap();
+            let site = Sites::from_str(&site).unwrap();
+            if enabled {
+                offline_sites.borrow_mut().insert(site);
+            } else {
+                offline_sites.borrow_mut().remove(&site);
+            }
+            let _ = offline_tx.blocking_send(Request::Offline { site, enabled });
+
+            reset_strip(&ui);
+            last(&offline_tx, &offline_sent, site);
+        });
+
+        ui.on_retry(move || {
+            let ui = retry_ui_weak.unwrap();
+
+            reset_strip(&ui);
+
+            if let Some(req) = sent.borrow().clone() {
+                let _ = retry_tx.blocking_send(req);
+            }
+        });
+
+        ui.on_subscribe(move |url| {
+            let ui = subscribe_ui_weak.unwrap();
+            ui.set_subscribe_error(SharedString::new());
+
+            let url = url.trim().to_owned();
+            if !url.is_empty() {
+                let _ = subscribe_tx.blocking_send(Request::Subscribe { url });
+            }
+        });
+
+        ui.on_show_page(move |url| {
+            let ui = page_ui_weak.unwrap();
+            ui.set_loaded(false);
+
+            let url = url.as_str().to_owned();
+            let _ = page_tx.blocking_send(Request::Image { url });
         });
 
         ui.on_open_url(move |site| {
@@ -68,7 +141,7 @@ impl Runnable for SlintFrontend {
             reset_strip(&ui);
 
             let site = Sites::from_str(&site).unwrap();
-            last(&last_tx, site);
+            last(&last_tx, &last_sent, site);
         });
 
         ui.on_random(move |site| {
@@ -77,7 +150,21 @@ impl Runnable for SlintFrontend {
             reset_strip(&ui);
 
             let site = Sites::from_str(&site).unwrap();
-            random(&random_tx, site);
+            random(&random_tx, &random_sent, site);
+        });
+
+        ui.on_by_date(move |site, date| {
+            let ui = date_ui_weak.unwrap();
+            let Ok(date) = NaiveDate::parse_from_str(date.trim(), "%Y-%m-%d") else {
+                ui.set_date_error(SharedString::from("Expected a date as YYYY-MM-DD"));
+                return;
+            };
+            ui.set_date_error(SharedString::new());
+
+            reset_strip(&ui);
+
+            let site = Sites::from_str(&site).unwrap();
+            send_strip(&date_tx, &date_sent, site, RequestStripType::ByDate(date));
         });
 
         ui.on_prev(move |site, idx| {
@@ -86,7 +173,7 @@ impl Runnable for SlintFrontend {
             reset_strip(&ui);
 
             let site = Sites::from_str(&site).unwrap();
-            prev(&prev_tx, site, idx);
+            prev(&prev_tx, &prev_sent, site, idx);
         });
 
         ui.on_next(move |site, idx| {
@@ -95,7 +182,7 @@ impl Runnable for SlintFrontend {
             reset_strip(&ui);
 
             let site = Sites::from_str(&site).unwrap();
-            next(&next_tx, site, idx);
+            next(&next_tx, &next_sent, site, idx);
         });
 
         ui.on_download(move |url, filename| {
@@ -117,7 +204,7 @@ impl Runnable for SlintFrontend {
         });
 
         handle.spawn(async move {
-            listener(rx, listener_ui_weak).await;
+            listener(rx, listener_tx, listener_ui_weak).await;
         });
 
         ui.invoke_site_selected(SharedString::from(Sites::ASofterWorld.to_string()));
@@ -132,6 +219,7 @@ fn reset_strip(ui: &AppWindow) {
     model.title = SharedString::from("LOADING...");
     ui.set_strip(model);
     ui.set_loaded(false);
+    ui.set_error(SharedString::new());
 }
 
 fn sites_to_model() -> ModelRc<SharedString> {
@@ -142,48 +230,62 @@ fn sites_to_model() -> ModelRc<SharedString> {
     ModelRc::from(sites.as_slice())
 }
 
-fn last(tx: &Sender<Request>, site: Sites) {
-    let req = Request::Strip {
-        site,
-        ty: RequestStripType::Last,
-    };
+/// Last `Request::Strip` sent
+type Sent = Rc<RefCell<Option<Request>>>;
+
+fn send_strip(tx: &Sender<Request>, sent: &Sent, site: Sites, ty: RequestStripType) {
+    let req = Request::Strip { site, ty };
+    *sent.borrow_mut() = Some(req.clone());
     let _ = tx.blocking_send(req);
 }
 
-fn random(tx: &Sender<Request>, site: Sites) {
-    let req = Request::Strip {
-        site,
-        ty: RequestStripType::Random,
-    };
-    let _ = tx.blocking_send(req);
+fn last(tx: &Sender<Request>, sent: &Sent, site: Sites) {
+    send_strip(tx, sent, site, RequestStripType::Last);
 }
 
-fn next(tx: &Sender<Request>, site: Sites, idx: i32) {
-    let idx = idx as usize;
-    let req = Request::Strip {
-        site,
-        ty: RequestStripType::Next(Some(idx)),
-    };
-    let _ = tx.blocking_send(req);
+fn random(tx: &Sender<Request>, sent: &Sent, site: Sites) {
+    send_strip(tx, sent, site, RequestStripType::Random);
 }
 
-fn prev(tx: &Sender<Request>, site: Sites, idx: i32) {
+fn next(tx: &Sender<Request>, sent: &Sent, site: Sites, idx: i32) {
     let idx = idx as usize;
-    let req = Request::Strip {
-        site,
-        ty: RequestStripType::Prev(Some(idx)),
-    };
+    send_strip(tx, sent, site, RequestStripType::Next(Some(idx)));
+}
 
-    let _ = tx.blocking_send(req);
+fn prev(tx: &Sender<Request>, sent: &Sent, site: Sites, idx: i32) {
+    let idx = idx as usize;
+    send_strip(tx, sent, site, RequestStripType::Prev(Some(idx)));
 }
 
-async fn listener(mut rx: Receiver<Response>, ui: Weak<AppWindow>) {
+async fn listener(mut rx: Receiver<Response>, tx: Sender<Request>, ui: Weak<AppWindow>) {
+    // Strip shown, or waiting for the bytes of one of its images
+    let mut current: Option<Strip> = None;
+
     while let Some(msg) = rx.recv().await {
         match msg {
-            Response::Strip(Some(strip)) => {
-                let buffer = load_image(&strip.url).await;
-                let _ = ui.upgrade_in_event_loop(move |ui| {
-                    if let Ok(buffer) = buffer {
+            Response::Strip {
+                strip: Ok(strip), ..
+            } => {
+                let url = strip.url.clone();
+                current = Some(strip);
+                let _ = tx.send(Request::Image { url }).await;
+            }
+            Response::Strip {
+                strip: Err(err), ..
+            } => {
+                current = None;
+                let _ = ui.upgrade_in_event_loop(move |ui| show_error(&ui, err.to_string()));
+            }
+            Response::Image { url, data } => {
+                let Some((strip, page)) = current.as_ref().and_then(|strip| {
+                    let page = strip.image_urls().iter().position(|image| *image == url)?;
+                    Some((strip.clone(), page))
+                }) else {
+                    continue;
+                };
+                let buffer = data.and_then(|data| load_image(&data));
+                let _ = ui.upgrade_in_event_loop(move |ui| match buffer {
+                    Ok(buffer) => {
                         ui.set_loaded(true);
                         let image = Image::from_rgba8(buffer);
                         let mut model = StripModel::default();
@@ -193,10 +295,35 @@ async fn listener(mut rx: Receiver<Response>, ui: Weak<AppWindow>) {
                         model.has_next = strip.has_next();
                         model.has_prev = strip.has_prev();
                         model.is_last = strip.is_last();
-                        model.url = SharedString::from(&strip.url);
+                        model.url = SharedString::from(&url);
+                        model.page = page as i32;
+                        let urls: Vec<_> = strip
+                            .image_urls()
+                            .into_iter()
+                            .map(SharedString::from)
+                            .collect();
+                        model.urls = ModelRc::from(urls.as_slice());
                         model.filename = SharedString::from(strip.file_name());
+                        model.alt_text = strip.alt_text.clone().unwrap_or_default().into();
+                        model.hover_text = strip.hover_text.clone().unwrap_or_default().into();
                         ui.set_strip(model);
                     }
+                    Err(err) => show_error(&ui, format!("{err:#}")),
+                });
+            }
+            Response::Subscribed(Ok(site)) => {
+                let _ = ui.upgrade_in_event_loop(move |ui| {
+                    let site = SharedString::from(site.to_string());
+                    ui.set_sites(sites_to_model());
+                    ui.set_site_current(site.clone());
+                    ui.set_feed_url(SharedString::new());
+                    ui.set_subscribe_error(SharedString::new());
+                    ui.invoke_site_selected(site);
+                });
+            }
+            Response::Subscribed(Err(err)) => {
+                let _ = ui.upgrade_in_event_loop(move |ui| {
+                    ui.set_subscribe_error(SharedString::from(err.to_string()));
                 });
             }
             Response::Download(_) => {
@@ -211,9 +338,16 @@ async fn listener(mut rx: Receiver<Response>, ui: Weak<AppWindow>) {
     }
 }
 
-async fn load_image(url: &str) -> Result<SharedPixelBuffer<Rgba8Pixel>> {
-    let data = reqwest::get(url).await?.bytes().await?;
+/// Replace the spinner with `err` and the Retry button
+fn show_error(ui: &AppWindow, err: String) {
+    let mut model = ui.get_strip();
+    model.title = SharedString::from("FAILED");
+    ui.set_strip(model);
+    ui.set_loaded(false);
+    ui.set_error(SharedString::from(err));
+}
 
+fn load_image(data: &[u8]) -> Result<SharedPixelBuffer<Rgba8Pixel>> {
     let image = ImageReader::new(Cursor::new(data))
         .with_guessed_format()?
         .decode()?
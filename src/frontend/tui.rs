@@ -0,0 +1,635 @@
+use std::{
+    collections::{BTreeMap, HashSet},
+    env,
+    io::{Cursor, Write, stdout},
+    path::PathBuf,
+    time::Duration,
+};
+
+use anyhow::Result;
+use base64::{Engine, engine::general_purpose::STANDARD};
+use image::{DynamicImage, ImageFormat, RgbImage, imageops::FilterType};
+use ratatui::{
+    DefaultTerminal, Frame,
+    buffer::Buffer,
+    crossterm::{
+        cursor::MoveTo,
+        event::{self, Event, KeyCode, KeyEventKind},
+        queue,
+        terminal::window_size,
+    },
+    layout::{Constraint, Flex, Layout, Rect},
+    style::{Color, Style, Stylize},
+    text::Line,
+    widgets::{Block, Clear, List, ListState, Paragraph, Widget},
+};
+use tokio::{
+    runtime::Handle,
+    sync::mpsc::{Receiver, Sender},
+};
+
+use crate::{
+    Sites, Strip, Url,
+    backend::{Request, RequestStripType, Response},
+    export::{ExportFormat, ExportRange},
+    mirror::has_mirror,
+    settings::config_errors,
+};
+
+use super::Runnable;
+
+/// Cell size assumed when the terminal doesn't report its size in pixels
+const DEFAULT_CELL_SIZE: (u32, u32) = (8, 16);
+
+/// Size of the chunks of a kitty graphics transmission
+const KITTY_CHUNK_SIZE: usize = 4096;
+
+const HELP: &str = "←/p prev  →/n next  l last  r random  [/] page  s sites  a add feed  o offline  d download  e export  q quit";
+
+#[derive(Default)]
+pub struct TuiFrontend;
+
+impl Runnable for TuiFrontend {
+    fn run(_handle: Handle, tx: Sender<Request>, rx: Receiver<Response>) -> Result<()> {
+        let terminal = ratatui::init();
+        let res = App::new(tx, rx).run(terminal);
+        ratatui::restore();
+        res
+    }
+}
+
+/// How the strip image is drawn on the terminal
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Graphics {
+    Kitty,
+    Sixel,
+    HalfBlocks,
+}
+
+impl Graphics {
+    /// `DAILY_STRIP_GRAPHICS` (`kitty`, `sixel` or `halfblocks`) overrides the detection
+    fn detect() -> Self {
+        let var = |name| env::var(name).unwrap_or_default().to_lowercase();
+        match var("DAILY_STRIP_GRAPHICS").as_str() {
+            "kitty" => return Graphics::Kitty,
+            "sixel" => return Graphics::Sixel,
+            "halfblocks" => return Graphics::HalfBlocks,
+            _ => {}
+        }
+
+        let (term, program) = (var("TERM"), var("TERM_PROGRAM"));
+        if !var("KITTY_WINDOW_ID").is_empty()
+            || term.contains("kitty")
+            || term.contains("ghostty")
+            || program == "wezterm"
+        {
+            Graphics::Kitty
+        } else if term.contains("sixel") || term.starts_with("foot") || term.starts_with("mlterm") {
+            Graphics::Sixel
+        } else {
+            Graphics::HalfBlocks
+        }
+    }
+}
+
+struct App {
+    tx: Sender<Request>,
+    rx: Receiver<Response>,
+    graphics: Graphics,
+    sites: Vec<Sites>,
+    site: Sites,
+    strip: Option<Strip>,
+    /// Strip request waiting for its answer
+    pending: Option<RequestStripType>,
+    /// Image of the strip being shown, for strips made of several images
+    page: usize,
+    image: Option<DynamicImage>,
+    /// Image resized for the area it was last rendered in
+    resized: Option<(Rect, RgbImage)>,
+    /// Area and url of the image last written with a graphics protocol
+    drawn: Option<(Rect, String)>,
+    picker: Option<ListState>,
+    /// Url of the feed being typed in, to subscribe to
+    feed_url: Option<String>,
+    /// Sites browsed from their mirror
+    offline: HashSet<Sites>,
+    download: Option<PathBuf>,
+    export: Option<PathBuf>,
+    status: String,
+    /// Broken configuration files, shown instead of the help until a key is pressed
+    config_errors: Vec<String>,
+    quit: bool,
+}
+
+impl App {
+    fn new(tx: Sender<Request>, rx: Receiver<Response>) -> Self {
+        Self {
+            tx,
+            rx,
+            graphics: Graphics::detect(),
+            sites: Sites::sites_sorted(),
+            site: Sites::default(),
+            strip: None,
+            pending: None,
+            page: 0,
+            image: None,
+            resized: None,
+            drawn: None,
+            picker: None,
+            feed_url: None,
+            offline: HashSet::new(),
+            download: None,
+            export: None,
+            status: String::new(),
+            config_errors: config_errors(),
+            quit: false,
+        }
+    }
+
+    fn run(mut self, mut terminal: DefaultTerminal) -> Result<()> {
+        self.request(RequestStripType::Last);
+
+        while !self.quit {
+            let mut image_area = None;
+            terminal.draw(|frame| image_area = self.draw(frame))?;
+
+            if self.graphics != Graphics::HalfBlocks {
+                let target = image_area.zip(self.page_url());
+                if target != self.drawn {
+                    // Wipe the previous image, the terminal doesn't know about it
+                    terminal.clear()?;
+                    terminal.draw(|frame| {
+                        self.draw(frame);
+                    })?;
+                    self.draw_graphics(image_area)?;
+                    self.drawn = target;
+                }
+            }
+
+            if event::poll(Duration::from_millis(50))?
+                && let Event::Key(key) = event::read()?
+                && key.kind == KeyEventKind::Press
+            {
+                self.handle_key(key.code);
+            }
+
+            while let Ok(response) = self.rx.try_recv() {
+                self.handle_response(response);
+            }
+        }
+        Ok(())
+    }
+
+    fn request(&mut self, ty: RequestStripType) {
+        self.pending = Some(ty);
+        self.strip = None;
+        self.page = 0;
+        self.image = None;
+        self.resized = None;
+        self.status = "Loading...".to_owned();
+        let _ = self.tx.blocking_send(Request::Strip {
+            site: self.site,
+            ty,
+        });
+    }
+
+    fn page_url(&self) -> Option<String> {
+        let strip = self.strip.as_ref()?;
+        strip.image_urls().get(self.page).map(|url| url.to_string())
+    }
+
+    fn show_page(&mut self, page: usize) {
+        let pages = self.strip.as_ref().map(|strip| strip.image_urls().len());
+        if page == self.page || pages.is_none_or(|pages| page >= pages) {
+            return;
+        }
+        self.page = page;
+        self.image = None;
+        self.resized = None;
+        if let Some(url) = self.page_url() {
+            let _ = self.tx.blocking_send(Request::Image { url });
+        }
+    }
+
+    fn handle_key(&mut self, code: KeyCode) {
+        self.config_errors.clear();
+        if let Some(feed_url) = self.feed_url.as_mut() {
+            match code {
+                KeyCode::Char(c) => feed_url.push(c),
+                KeyCode::Backspace => {
+                    feed_url.pop();
+                }
+                KeyCode::Enter if !feed_url.trim().is_empty() => {
+                    let url = feed_url.trim().to_owned();
+                    self.status = format!("Subscribing to {url}...");
+                    self.feed_url = None;
+                    let _ = self.tx.blocking_send(Request::Subscribe { url });
+                }
+                KeyCode::Esc => self.feed_url = None,
+                _ => {}
+            }
+            return;
+        }
+
+        if let Some(picker) = self.picker.as_mut() {
+            match code {
+                KeyCode::Up | KeyCode::Char('k') => picker.select_previous(),
+                KeyCode::Down | KeyCode::Char('j') => picker.select_next(),
+                KeyCode::Enter => {
+                    if let Some(site) = picker.selected().and_then(|idx| self.sites.get(idx)) {
+                        self.site = *site;
+                        self.picker = None;
+                        self.request(RequestStripType::Last);
+                    }
+                }
+                KeyCode::Esc | KeyCode::Char('q') => self.picker = None,
+                _ => {}
+            }
+            return;
+        }
+
+        let strip = self.strip.as_ref();
+        match code {
+            KeyCode::Char('q') | KeyCode::Esc => self.quit = true,
+            KeyCode::Left | KeyCode::Char('p') if strip.is_some_and(Strip::has_prev) => {
+                let idx = strip.map(|strip| strip.idx);
+                self.request(RequestStripType::Prev(idx));
+            }
+            KeyCode::Right | KeyCode::Char('n') if strip.is_some_and(Strip::has_next) => {
+                let idx = strip.map(|strip| strip.idx);
+                self.request(RequestStripType::Next(idx));
+            }
+            KeyCode::Char('l') if !strip.is_some_and(Strip::is_last) => {
+                self.request(RequestStripType::Last)
+            }
+            KeyCode::Char('r') => self.request(RequestStripType::Random),
+            KeyCode::Char('[') => self.show_page(self.page.saturating_sub(1)),
+            KeyCode::Char(']') => self.show_page(self.page + 1),
+            KeyCode::Char('s') | KeyCode::Tab => {
+                let selected = self.sites.iter().position(|site| *site == self.site);
+                self.picker = Some(ListState::default().with_selected(selected));
+            }
+            KeyCode::Char('a') => self.feed_url = Some(String::new()),
+            KeyCode::Char('o') => {
+                let enabled = !self.offline.contains(&self.site);
+                if enabled && !has_mirror(self.site) {
+                    self.status = format!(
+                        "No mirror of {}, run `daily_strip mirror {}` first",
+                        self.site,
+                        self.site.id()
+                    );
+                    return;
+                }
+                if enabled {
+                    self.offline.insert(self.site);
+                } else {
+                    self.offline.remove(&self.site);
+                }
+                let _ = self.tx.blocking_send(Request::Offline {
+                    site: self.site,
+                    enabled,
+                });
+                self.request(RequestStripType::Last);
+            }
+            KeyCode::Char('d') => {
+                if let Some(strip) = strip {
+                    let Some(url) = self.page_url() else {
+                        self.status = "Nothing to download yet".to_owned();
+                        return;
+                    };
+                    let path = PathBuf::from(strip.file_name());
+                    self.status = format!("Downloading {}...", path.display());
+                    self.download = Some(path.clone());
+                    let _ = self.tx.blocking_send(Request::Download { path, url });
+                }
+            }
+            KeyCode::Char('e') => {
+                let format = ExportFormat::default();
+                let path = PathBuf::from(format!("{}.{}", self.site.id(), format.extension()));
+                self.status = format!("Exporting {}...", path.display());
+                self.export = Some(path.clone());
+                let _ = self.tx.blocking_send(Request::Export {
+                    site: self.site,
+                    range: ExportRange::All,
+                    format,
+                    path,
+                });
+            }
+            _ => {}
+        }
+    }
+
+    fn handle_response(&mut self, response: Response) {
+        match response {
+            // Answers to the requests made before the last one are dropped
+            Response::Strip { site, ty, strip }
+                if site == self.site && self.pending == Some(ty) =>
+            {
+                self.pending = None;
+                match strip {
+                    Ok(strip) => {
+                        let url = strip.url.clone();
+                        self.status = format!("{} #{}", strip.title, strip.idx);
+                        self.strip = Some(strip);
+                        let _ = self.tx.blocking_send(Request::Image { url });
+                    }
+                    Err(err) => self.status = format!("Failed to load the strip: {err}"),
+                }
+            }
+            Response::Image { url, data }
+                if self.page_url().is_some_and(|page_url| page_url == url) =>
+            {
+                match data.and_then(|data| Ok(image::load_from_memory(&data)?)) {
+                    Ok(image) => self.image = Some(image),
+                    Err(err) => self.status = format!("Failed to load the image: {err}"),
+                }
+            }
+            Response::Download(res) => {
+                let path = self.download.take().unwrap_or_default();
+                self.status = match res {
+                    Ok(_) => format!("Saved {}", path.display()),
+                    Err(err) => format!("Download failed: {err}"),
+                };
+            }
+            Response::ExportProgress { done, total } => {
+                self.status = format!("Exporting {done}/{total}...");
+            }
+            Response::Export(res) => {
+                let path = self.export.take().unwrap_or_default();
+                self.status = match res {
+                    Ok(report) => format!(
+                        "Exported {} strips to {}, {} failed",
+                        report.exported,
+                        path.display(),
+                        report.failed
+                    ),
+                    Err(err) => format!("Export failed: {err}"),
+                };
+            }
+            Response::Subscribed(Ok(site)) => {
+                self.sites = Sites::sites_sorted();
+                self.site = site;
+                self.request(RequestStripType::Last);
+            }
+            Response::Subscribed(Err(err)) => self.status = format!("Subscription failed: {err}"),
+            _ => {}
+        }
+    }
+
+    /// Returns the area reserved to the image, when there is one to show
+    fn draw(&mut self, frame: &mut Frame) -> Option<Rect> {
+        let [main, status, help] = Layout::vertical([
+            Constraint::Min(1),
+            Constraint::Length(1),
+            Constraint::Length(1),
+        ])
+        .areas(frame.area());
+
+        let title = self
+            .strip
+            .as_ref()
+            .map(|strip| match strip.image_urls().len() {
+                1 => strip.title.clone(),
+                pages => format!("{} ({}/{pages})", strip.title, self.page + 1),
+            })
+            .unwrap_or_default();
+        // No hovering in a terminal, the hidden text goes on the bottom border
+        let caption = self
+            .strip
+            .as_ref()
+            .and_then(|strip| strip.hover_text.clone().or_else(|| strip.alt_text.clone()))
+            .unwrap_or_default();
+        let block = Block::bordered()
+            .title(Line::from(title).centered())
+            .title_bottom(Line::from(caption).centered().italic());
+        let inner = block.inner(main);
+        frame.render_widget(block, main);
+
+        let mut image_area = None;
+        match self.image.as_ref() {
+            None => frame.render_widget(
+                Paragraph::new("Loading...").centered(),
+                Rect {
+                    y: inner.y + inner.height / 2,
+                    height: 1,
+                    ..inner
+                },
+            ),
+            Some(image) if self.graphics == Graphics::HalfBlocks => {
+                if self.resized.as_ref().is_none_or(|(area, _)| *area != inner) {
+                    let resized = image
+                        .resize(
+                            inner.width as u32,
+                            inner.height as u32 * 2,
+                            FilterType::Triangle,
+                        )
+                        .to_rgb8();
+                    self.resized = Some((inner, resized));
+                }
+                if let Some((_, resized)) = self.resized.as_ref() {
+                    frame.render_widget(HalfBlocks(resized), inner);
+                }
+            }
+            Some(_) => {
+                // Leave the cells alone, the image is written right after the frame
+                for y in inner.top()..inner.bottom() {
+                    for x in inner.left()..inner.right() {
+                        if let Some(cell) = frame.buffer_mut().cell_mut((x, y)) {
+                            cell.set_skip(true);
+                        }
+                    }
+                }
+                image_area = Some(inner);
+            }
+        }
+
+        let homepage = self.site.homepage();
+        let mode = if self.offline.contains(&self.site) {
+            " [offline]".yellow()
+        } else {
+            "".into()
+        };
+        frame.render_widget(
+            Line::from(vec![
+                self.site.to_string().bold(),
+                mode,
+                " ".into(),
+                homepage.blue().underlined(),
+                "  ".into(),
+                self.status.as_str().into(),
+            ]),
+            status,
+        );
+        let help_line = match self.config_errors.is_empty() {
+            true => Line::from(HELP).dim(),
+            false => Line::from(format!("Ignoring {}", self.config_errors.join(", "))).red(),
+        };
+        frame.render_widget(help_line, help);
+
+        if let Some(picker) = self.picker.as_mut() {
+            let [area] = Layout::horizontal([Constraint::Length(40)])
+                .flex(Flex::Center)
+                .areas(main);
+            let [area] = Layout::vertical([Constraint::Length(self.sites.len() as u16 + 2)])
+                .flex(Flex::Center)
+                .areas(area);
+            let list = List::new(self.sites.iter().map(ToString::to_string))
+                .block(Block::bordered().title("Sites"))
+                .highlight_style(Style::new().reversed());
+            frame.render_widget(Clear, area);
+            frame.render_stateful_widget(list, area, picker);
+            // The popup hides the image, draw it again when closed
+            return None;
+        }
+
+        if let Some(feed_url) = self.feed_url.as_deref() {
+            let [area] = Layout::horizontal([Constraint::Percentage(60)])
+                .flex(Flex::Center)
+                .areas(main);
+            let [area] = Layout::vertical([Constraint::Length(3)])
+                .flex(Flex::Center)
+                .areas(area);
+            let input = Paragraph::new(format!("{feed_url}▏"))
+                .block(Block::bordered().title("Feed url, Enter to subscribe"));
+            frame.render_widget(Clear, area);
+            frame.render_widget(input, area);
+            return None;
+        }
+
+        image_area
+    }
+
+    fn draw_graphics(&self, area: Option<Rect>) -> Result<()> {
+        let (Some(area), Some(image)) = (area, self.image.as_ref()) else {
+            return Ok(());
+        };
+
+        let (cell_width, cell_height) = match window_size() {
+            Ok(size) if size.width > 0 && size.columns > 0 => (
+                size.width as u32 / size.columns as u32,
+                size.height as u32 / size.rows as u32,
+            ),
+            _ => DEFAULT_CELL_SIZE,
+        };
+        let image = image
+            .resize(
+                area.width as u32 * cell_width,
+                area.height as u32 * cell_height,
+                FilterType::Triangle,
+            )
+            .to_rgb8();
+        let columns = image.width().div_ceil(cell_width) as u16;
+        let rows = image.height().div_ceil(cell_height) as u16;
+        let x = area.x + area.width.saturating_sub(columns) / 2;
+        let y = area.y + area.height.saturating_sub(rows) / 2;
+
+        let mut out = stdout();
+        queue!(out, MoveTo(x, y))?;
+        match self.graphics {
+            Graphics::Kitty => write_kitty(&mut out, &image, columns, rows)?,
+            Graphics::Sixel => out.write_all(sixel(&image).as_bytes())?,
+            Graphics::HalfBlocks => {}
+        }
+        out.flush()?;
+        Ok(())
+    }
+}
+
+/// Each cell shows two pixels, the upper one as foreground of `▀` and the lower one as background
+struct HalfBlocks<'a>(&'a RgbImage);
+
+impl Widget for HalfBlocks<'_> {
+    fn render(self, area: Rect, buf: &mut Buffer) {
+        let image = self.0;
+        let columns = image.width() as u16;
+        let rows = image.height().div_ceil(2) as u16;
+        let x0 = area.x + area.width.saturating_sub(columns) / 2;
+        let y0 = area.y + area.height.saturating_sub(rows) / 2;
+
+        for y in (0..image.height()).step_by(2) {
+            for x in 0..image.width() {
+                let top = image.get_pixel(x, y);
+                let bottom = image.get_pixel(x, (y + 1).min(image.height() - 1));
+                if let Some(cell) = buf.cell_mut((x0 + x as u16, y0 + (y / 2) as u16)) {
+                    cell.set_char('▀')
+                        .set_fg(Color::Rgb(top[0], top[1], top[2]))
+                        .set_bg(Color::Rgb(bottom[0], bottom[1], bottom[2]));
+                }
+            }
+        }
+    }
+}
+
+fn write_kitty(out: &mut impl Write, image: &RgbImage, columns: u16, rows: u16) -> Result<()> {
+    let mut png = Cursor::new(Vec::new());
+    image.write_to(&mut png, ImageFormat::Png)?;
+    let data = STANDARD.encode(png.into_inner());
+
+    // Remove every image shown before
+    out.write_all(b"\x1b_Ga=d,d=A,q=2\x1b\\")?;
+    let mut chunks = data.as_bytes().chunks(KITTY_CHUNK_SIZE).peekable();
+    let mut first = true;
+    while let Some(chunk) = chunks.next() {
+        let more = chunks.peek().is_some() as u8;
+        if first {
+            write!(
+                out,
+                "\x1b_Ga=T,f=100,q=2,C=1,c={columns},r={rows},m={more};"
+            )?;
+            first = false;
+        } else {
+            write!(out, "\x1b_Gm={more};")?;
+        }
+        out.write_all(chunk)?;
+        out.write_all(b"\x1b\\")?;
+    }
+    Ok(())
+}
+
+/// Encode the image as sixels using a 6x6x6 color cube
+fn sixel(image: &RgbImage) -> String {
+    let (width, height) = image.dimensions();
+    let level = |channel: u8| (channel as usize * 5 + 127) / 255;
+    let color = |x, y| {
+        let pixel = image.get_pixel(x, y);
+        level(pixel[0]) * 36 + level(pixel[1]) * 6 + level(pixel[2])
+    };
+
+    let mut out = format!("\x1bPq\"1;1;{width};{height}");
+    for idx in 0..216 {
+        let (r, g, b) = (idx / 36, idx / 6 % 6, idx % 6);
+        out += &format!("#{idx};2;{};{};{}", r * 20, g * 20, b * 20);
+    }
+
+    for band in (0..height).step_by(6) {
+        let mut colors: BTreeMap<usize, Vec<u8>> = BTreeMap::new();
+        for y in band..(band + 6).min(height) {
+            for x in 0..width {
+                let bits = colors
+                    .entry(color(x, y))
+                    .or_insert_with(|| vec![0; width as usize]);
+                bits[x as usize] |= 1 << (y - band);
+            }
+        }
+
+        for (idx, bits) in colors {
+            out += &format!("#{idx}");
+            let mut bits = bits.into_iter().peekable();
+            while let Some(value) = bits.next() {
+                let mut run = 1;
+                while bits.next_if_eq(&value).is_some() {
+                    run += 1;
+                }
+                let sixel = char::from(63 + value);
+                match run {
+                    1..=3 => out.extend(std::iter::repeat_n(sixel, run)),
+                    _ => out += &format!("!{run}{sixel}"),
+                }
+            }
+            out.push('$');
+        }
+        out.push('-');
+    }
+    out += "\x1b\\";
+    out
+}
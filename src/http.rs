@@ -0,0 +1,305 @@
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex, OnceLock},
+    time::Duration,
+};
+
+use anyhow::Result;
+use reqwest::{
+    Client, IntoUrl, Proxy, Response, StatusCode, Url,
+    header::{ETAG, IF_MODIFIED_SINCE, IF_NONE_MATCH, LAST_MODIFIED},
+};
+use serde::{Deserialize, Serialize};
+use tokio::time::{Instant, sleep, sleep_until};
+
+use crate::settings::Settings;
+
+/// Identifies the app to the sites, so that their owners know who is knocking
+pub const DEFAULT_USER_AGENT: &str = concat!(
+    "daily_strip/",
+    env!("CARGO_PKG_VERSION"),
+    " (+https://github.com/newfla/daily-strip)"
+);
+
+/// `[http]` table of the settings file, durations are in seconds unless stated otherwise
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct HttpSettings {
+    /// Whole request, body included
+    pub timeout: u64,
+    pub connect_timeout: u64,
+    /// Attempts after the first one on timeouts, connection failures, 429 and 5xx statuses
+    pub retries: u32,
+    /// Wait before the first retry in milliseconds, doubled after each attempt
+    pub backoff: u64,
+    pub user_agent: String,
+    /// Proxy for every request, e.g. `http://localhost:3128`, the system one when unset
+    pub proxy: Option<String>,
+    /// Minimum time between two requests to the same host in milliseconds
+    pub host_interval: u64,
+}
+
+impl Default for HttpSettings {
+    fn default() -> Self {
+        Self {
+            timeout: 30,
+            connect_timeout: 10,
+            retries: 2,
+            backoff: 500,
+            user_agent: DEFAULT_USER_AGENT.to_owned(),
+            proxy: None,
+            host_interval: 100,
+        }
+    }
+}
+
+/// Validators of a response, sent back so that unchanged content isn't downloaded again
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Validator {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub etag: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub last_modified: Option<String>,
+}
+
+impl Validator {
+    /// `None` when the response carries neither `ETag` nor `Last-Modified`
+    fn from_response(res: &Response) -> Option<Self> {
+        let header = |name| {
+            res.headers()
+                .get(name)
+                .and_then(|value| value.to_str().ok())
+                .map(str::to_owned)
+        };
+        let validator = Self {
+            etag: header(ETAG),
+            last_modified: header(LAST_MODIFIED),
+        };
+        (validator != Self::default()).then_some(validator)
+    }
+}
+
+/// Validators by url
+pub type Validators = HashMap<String, Validator>;
+
+/// HTTP client shared by the fetchers and the image cache, cheap to clone.
+///
+/// Failing statuses are turned into errors, transient failures are retried with an exponential
+/// backoff and requests to the same host are spaced by `host_interval`.
+#[derive(Clone)]
+pub struct HttpClient {
+    client: Client,
+    settings: Arc<HttpSettings>,
+    /// Earliest time the next request to each host may start
+    hosts: Arc<Mutex<HashMap<String, Instant>>>,
+}
+
+impl HttpClient {
+    pub fn new(settings: HttpSettings) -> Result<Self> {
+        Ok(Self {
+            client: Self::builder(&settings)?.build()?,
+            settings: Arc::new(settings),
+            hosts: Arc::default(),
+        })
+    }
+
+    /// Client configured by the settings file, built on first use.
+    ///
+    /// A broken settings file is ignored for the defaults, [`config_errors`] tells why.
+    ///
+    /// [`config_errors`]: crate::settings::config_errors
+    pub fn shared() -> Self {
+        static SHARED: OnceLock<HttpClient> = OnceLock::new();
+        SHARED
+            .get_or_init(|| {
+                let settings = Settings::load().unwrap_or_default();
+                Self::new(settings.http)
+                    .or_else(|_| Self::new(HttpSettings::default()))
+                    .expect("default HTTP client")
+            })
+            .clone()
+    }
+
+    /// Same client trusting any certificate, for sites whose one is broken.
+    ///
+    /// The host spacing is shared with `self`.
+    pub(crate) fn accepting_invalid_certs(&self) -> Result<Self> {
+        Ok(Self {
+            client: Self::builder(&self.settings)?
+                .danger_accept_invalid_certs(true)
+                .build()?,
+            settings: self.settings.clone(),
+            hosts: self.hosts.clone(),
+        })
+    }
+
+    fn builder(settings: &HttpSettings) -> Result<reqwest::ClientBuilder> {
+        let mut builder = Client::builder()
+            .timeout(Duration::from_secs(settings.timeout))
+            .connect_timeout(Duration::from_secs(settings.connect_timeout))
+            .user_agent(&settings.user_agent);
+        if let Some(proxy) = settings.proxy.as_deref() {
+            builder = builder.proxy(Proxy::all(proxy)?);
+        }
+        Ok(builder)
+    }
+
+    /// Successful response to a GET of `url`
+    pub async fn get(&self, url: impl IntoUrl) -> Result<Response> {
+        self.send(url.into_url()?, None).await
+    }
+
+    /// GET of `url` made conditional by `validator`, `None` when the content didn't change.
+    ///
+    /// The response comes with its own validator, to be sent next time.
+    pub async fn get_if_modified(
+        &self,
+        url: impl IntoUrl,
+        validator: Option<&Validator>,
+    ) -> Result<Option<(Response, Option<Validator>)>> {
+        let res = self.send(url.into_url()?, validator).await?;
+        if res.status() == StatusCode::NOT_MODIFIED {
+            return Ok(None);
+        }
+        let validator = Validator::from_response(&res);
+        Ok(Some((res, validator)))
+    }
+
+    async fn send(&self, url: Url, validator: Option<&Validator>) -> Result<Response> {
+        let mut backoff = Duration::from_millis(self.settings.backoff);
+        let mut attempt = 0;
+        loop {
+            self.wait_turn(&url).await;
+            let mut req = self.client.get(url.clone());
+            if let Some(etag) = validator.and_then(|validator| validator.etag.as_deref()) {
+                req = req.header(IF_NONE_MATCH, etag);
+            }
+            if let Some(date) = validator.and_then(|validator| validator.last_modified.as_deref()) {
+                req = req.header(IF_MODIFIED_SINCE, date);
+            }
+            let res = req.send().await.and_then(Response::error_for_status);
+            match res {
+                Err(err) if attempt < self.settings.retries && is_transient(&err) => {
+                    attempt += 1;
+                    sleep(backoff).await;
+                    backoff *= 2;
+                }
+                res => return Ok(res?),
+            }
+        }
+    }
+
+    pub async fn text(&self, url: impl IntoUrl) -> Result<String> {
+        Ok(self.get(url).await?.text().await?)
+    }
+
+    pub async fn bytes(&self, url: impl IntoUrl) -> Result<Vec<u8>> {
+        Ok(self.get(url).await?.bytes().await?.to_vec())
+    }
+
+    /// Book the next slot of the host of `url` and wait for it
+    async fn wait_turn(&self, url: &Url) {
+        let Some(host) = url.host_str() else {
+            return;
+        };
+        let key = format!("{host}:{}", url.port_or_known_default().unwrap_or_default());
+        let interval = Duration::from_millis(self.settings.host_interval);
+        let turn = {
+            let mut hosts = self.hosts.lock().unwrap();
+            let now = Instant::now();
+            let turn = hosts.get(&key).map_or(now, |next| (*next).max(now));
+            hosts.insert(key, turn + interval);
+            turn
+        };
+        sleep_until(turn).await;
+    }
+}
+
+/// Whether trying again later may succeed
+fn is_transient(err: &reqwest::Error) -> bool {
+    match err.status() {
+        Some(status) => status.is_server_error() || status == StatusCode::TOO_MANY_REQUESTS,
+        None => err.is_timeout() || err.is_connect(),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::{
+        sync::{
+            Arc,
+            atomic::{AtomicUsize, Ordering},
+        },
+        time::Duration,
+    };
+
+    use tokio::{
+        io::{AsyncReadExt, AsyncWriteExt},
+        net::TcpListener,
+        spawn,
+        time::Instant,
+    };
+
+    use super::{HttpClient, HttpSettings};
+
+    /// Server answering 503 to the first `failures` requests, then `status`
+    async fn flaky_server(failures: usize, status: &'static str) -> (String, Arc<AtomicUsize>) {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}/", listener.local_addr().unwrap());
+        let hits = Arc::new(AtomicUsize::new(0));
+        let counter = hits.clone();
+        spawn(async move {
+            while let Ok((mut stream, _)) = listener.accept().await {
+                let mut buf = [0; 1024];
+                let _ = stream.read(&mut buf).await;
+                let status = match counter.fetch_add(1, Ordering::SeqCst) < failures {
+                    true => "503 Service Unavailable",
+                    false => status,
+                };
+                let response = format!(
+                    "HTTP/1.1 {status}\r\nContent-Length: 2\r\nConnection: close\r\n\r\nok"
+                );
+                let _ = stream.write_all(response.as_bytes()).await;
+            }
+        });
+        (url, hits)
+    }
+
+    fn client(retries: u32, host_interval: u64) -> HttpClient {
+        HttpClient::new(HttpSettings {
+            retries,
+            backoff: 10,
+            host_interval,
+            ..Default::default()
+        })
+        .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_http_retries() {
+        let (url, hits) = flaky_server(2, "200 OK").await;
+        assert_eq!(client(2, 0).text(&url).await.unwrap(), "ok");
+        assert_eq!(hits.load(Ordering::SeqCst), 3);
+
+        let (url, hits) = flaky_server(2, "200 OK").await;
+        assert!(client(1, 0).text(&url).await.is_err());
+        assert_eq!(hits.load(Ordering::SeqCst), 2);
+
+        // Missing pages won't show up by asking again
+        let (url, hits) = flaky_server(0, "404 Not Found").await;
+        assert!(client(2, 0).text(&url).await.is_err());
+        assert_eq!(hits.load(Ordering::SeqCst), 1);
+    }
+
+    #[tokio::test]
+    async fn test_http_host_interval() {
+        let (url, hits) = flaky_server(0, "200 OK").await;
+        let client = client(0, 50);
+        let start = Instant::now();
+        for _ in 0..3 {
+            client.text(&url).await.unwrap();
+        }
+        assert_eq!(hits.load(Ordering::SeqCst), 3);
+        assert!(start.elapsed() >= Duration::from_millis(100));
+    }
+}
@@ -1,103 +1,311 @@
+use std::{fmt, str::FromStr};
+
 use anyhow::Result;
 use async_trait::async_trait;
+use chrono::NaiveDate;
+use serde::{Deserialize, Serialize};
 use strum::IntoEnumIterator;
-use strum_macros::{Display, EnumIter, EnumString};
+use strum_macros::EnumIter;
 use thiserror::Error;
 
+use crate::custom::CustomSite;
+
 pub mod backend;
+pub mod cache;
+#[cfg(feature = "cli")]
+pub mod cli;
+pub mod custom;
+pub mod export;
+pub mod feed;
 pub mod fetcher;
 pub mod frontend;
+pub mod http;
+pub mod mirror;
+#[cfg(feature = "server")]
+pub mod server;
+pub mod settings;
 
-#[derive(Debug, Default, Display, Clone, Copy, EnumIter, EnumString, Hash, PartialEq, Eq)]
+#[derive(Debug, Default, Clone, Copy, EnumIter, Hash, PartialEq, Eq, Serialize, Deserialize)]
 /// Supported strip sites
 #[non_exhaustive]
 pub enum Sites {
     #[default]
-    #[strum(to_string = "turnoff.us")]
     TurnoffUs,
-    #[strum(to_string = "MonkeyUser")]
     MonkeyUser,
-    #[strum(to_string = "Bonkers World")]
     BonkersWorld,
-    #[strum(to_string = "Goomics")]
     Goomics,
-    #[strum(to_string = "xkcd")]
     Xkcd,
-    #[strum(to_string = "Dinosaur Comics")]
     DinosaurComics,
-    #[strum(to_string = "Oglaf [NSFW]")]
     Oglaf,
-    #[strum(to_string = "CTRL+ALT+DEL")]
     CadComics,
-    #[strum(to_string = "The Joy of Tech")]
     JoyOfTech,
-    #[strum(to_string = "Good Tech Things")]
     GoodTechThings,
-    #[strum(to_string = "Three Word Phrase")]
     ThreeWordPhrase,
-    #[strum(to_string = "a softer world")]
     ASofterWorld,
-    #[strum(to_string = "BUTTERSAFE")]
     ButterSafe,
-    #[strum(to_string = "Questionable Content")]
     QuestionableContent,
-    #[strum(to_string = "Work Chronicles")]
     WorkChronicles,
-    #[strum(to_string = "Junior Scientist Power Hour")]
     JSPowerHour,
-    #[strum(to_string = "Buttercup Festival")]
     ButtercupFestival,
-    #[strum(to_string = "achewood")]
     Achewood,
-    #[strum(to_string = "Cat and Girl")]
     CatAndGirl,
-    #[strum(to_string = "Diesel Sweeties #1.0")]
     DieselSweeties1_0,
-    #[strum(to_string = "Diesel Sweeties #3.0")]
     DieselSweeties3_0,
-    #[strum(to_string = "Poorly Drawn Lines")]
     PoorlyDrawnLines,
-    #[strum(to_string = "Piled Higher and Deeper")]
     PiledHigherAndDeeper,
-    #[strum(to_string = "Gunnerkrigg Court")]
     GunnerkriggCourt,
+    /// Added at runtime, listed by [`Sites::all`] but not by `Sites::iter()`
+    #[strum(disabled)]
+    Custom(CustomSite),
 }
 
 impl Sites {
+    /// Built-in sites followed by the registered custom ones
+    pub fn all() -> Vec<Sites> {
+        Sites::iter().chain(custom::sites()).collect()
+    }
+
     pub fn sites_sorted() -> Vec<Sites> {
-        let mut sites: Vec<_> = Sites::iter().collect();
+        let mut sites = Sites::all();
         sites.sort_by_key(|site| site.to_string().to_lowercase());
         sites
     }
+
+    /// Short identifier of the site, suitable for command lines and urls
+    pub fn id(&self) -> &'static str {
+        match self {
+            Sites::TurnoffUs => "turnoff-us",
+            Sites::MonkeyUser => "monkey-user",
+            Sites::BonkersWorld => "bonkers-world",
+            Sites::Goomics => "goomics",
+            Sites::Xkcd => "xkcd",
+            Sites::DinosaurComics => "dinosaur-comics",
+            Sites::Oglaf => "oglaf",
+            Sites::CadComics => "cad-comics",
+            Sites::JoyOfTech => "joy-of-tech",
+            Sites::GoodTechThings => "good-tech-things",
+            Sites::ThreeWordPhrase => "three-word-phrase",
+            Sites::ASofterWorld => "a-softer-world",
+            Sites::ButterSafe => "buttersafe",
+            Sites::QuestionableContent => "questionable-content",
+            Sites::WorkChronicles => "work-chronicles",
+            Sites::JSPowerHour => "js-power-hour",
+            Sites::ButtercupFestival => "buttercup-festival",
+            Sites::Achewood => "achewood",
+            Sites::CatAndGirl => "cat-and-girl",
+            Sites::DieselSweeties1_0 => "diesel-sweeties-1-0",
+            Sites::DieselSweeties3_0 => "diesel-sweeties-3-0",
+            Sites::PoorlyDrawnLines => "poorly-drawn-lines",
+            Sites::PiledHigherAndDeeper => "phd",
+            Sites::GunnerkriggCourt => "gunnerkrigg-court",
+            Sites::Custom(site) => &site.info().id,
+        }
+    }
+
+    pub fn from_id(id: &str) -> Option<Sites> {
+        Sites::all().into_iter().find(|site| site.id() == id)
+    }
+
+    /// Whether the strips are not safe for work
+    pub fn is_nsfw(&self) -> bool {
+        match self {
+            Sites::Oglaf => true,
+            Sites::Custom(site) => site.info().nsfw,
+            _ => false,
+        }
+    }
+}
+
+impl fmt::Display for Sites {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Sites::TurnoffUs => "turnoff.us",
+            Sites::MonkeyUser => "MonkeyUser",
+            Sites::BonkersWorld => "Bonkers World",
+            Sites::Goomics => "Goomics",
+            Sites::Xkcd => "xkcd",
+            Sites::DinosaurComics => "Dinosaur Comics",
+            Sites::Oglaf => "Oglaf [NSFW]",
+            Sites::CadComics => "CTRL+ALT+DEL",
+            Sites::JoyOfTech => "The Joy of Tech",
+            Sites::GoodTechThings => "Good Tech Things",
+            Sites::ThreeWordPhrase => "Three Word Phrase",
+            Sites::ASofterWorld => "a softer world",
+            Sites::ButterSafe => "BUTTERSAFE",
+            Sites::QuestionableContent => "Questionable Content",
+            Sites::WorkChronicles => "Work Chronicles",
+            Sites::JSPowerHour => "Junior Scientist Power Hour",
+            Sites::ButtercupFestival => "Buttercup Festival",
+            Sites::Achewood => "achewood",
+            Sites::CatAndGirl => "Cat and Girl",
+            Sites::DieselSweeties1_0 => "Diesel Sweeties #1.0",
+            Sites::DieselSweeties3_0 => "Diesel Sweeties #3.0",
+            Sites::PoorlyDrawnLines => "Poorly Drawn Lines",
+            Sites::PiledHigherAndDeeper => "Piled Higher and Deeper",
+            Sites::GunnerkriggCourt => "Gunnerkrigg Court",
+            Sites::Custom(site) => return site.fmt(f),
+        };
+        f.write_str(name)
+    }
+}
+
+/// Parse the display name of a site
+impl FromStr for Sites {
+    type Err = strum::ParseError;
+
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        Sites::all()
+            .into_iter()
+            .find(|site| site.to_string() == name)
+            .ok_or(strum::ParseError::VariantNotFound)
+    }
 }
 
 #[async_trait]
 pub trait Fetcher {
     async fn reload(&mut self) -> Result<()>;
+    /// Fetch only the strips newer than the known ones, keeping the `idx` of those already known.
+    ///
+    /// Returns the number of strips added to the archive.
+    async fn refresh(&mut self) -> Result<usize>;
     async fn last(&self) -> Result<Strip>;
     async fn random(&self) -> Result<Strip>;
+    async fn get(&self, idx: usize) -> Result<Strip>;
     async fn next(&self, idx: usize) -> Result<Strip>;
     async fn prev(&self, idx: usize) -> Result<Strip>;
+    /// Strip published closest to `date`, among those whose publication date is known
+    async fn by_date(&self, date: NaiveDate) -> Result<Strip>;
+    /// Url of the page the strip at `idx` was found on, before resolving its image
+    fn page_url(&self, idx: usize) -> Option<String>;
+    /// Strips in the archive, the `idx` of the last one plus one, when known without fetching
+    fn strip_count(&self) -> Option<usize> {
+        None
+    }
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
-enum StripType {
+#[async_trait]
+impl<F: Fetcher + Send + Sync + ?Sized> Fetcher for Box<F> {
+    async fn reload(&mut self) -> Result<()> {
+        (**self).reload().await
+    }
+
+    async fn refresh(&mut self) -> Result<usize> {
+        (**self).refresh().await
+    }
+
+    async fn last(&self) -> Result<Strip> {
+        (**self).last().await
+    }
+
+    async fn random(&self) -> Result<Strip> {
+        (**self).random().await
+    }
+
+    async fn get(&self, idx: usize) -> Result<Strip> {
+        (**self).get(idx).await
+    }
+
+    async fn next(&self, idx: usize) -> Result<Strip> {
+        (**self).next(idx).await
+    }
+
+    async fn prev(&self, idx: usize) -> Result<Strip> {
+        (**self).prev(idx).await
+    }
+
+    async fn by_date(&self, date: NaiveDate) -> Result<Strip> {
+        (**self).by_date(date).await
+    }
+
+    fn page_url(&self, idx: usize) -> Option<String> {
+        (**self).page_url(idx)
+    }
+
+    fn strip_count(&self) -> Option<usize> {
+        (**self).strip_count()
+    }
+}
+
+/// Where a strip stands in its archive, `First` being the newest strip
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum StripType {
     First,
     Unknown,
     Last,
+    /// Only strip of the archive
     Unique,
 }
 
-#[derive(Debug, Clone)]
+impl StripType {
+    /// Type of the strip at `pos` in an archive of `len` strips sorted oldest first
+    pub fn at(pos: usize, len: usize) -> Self {
+        match (pos, len) {
+            (_, 1) => StripType::Unique,
+            (0, _) => StripType::Last,
+            (pos, len) if pos + 1 == len => StripType::First,
+            _ => StripType::Unknown,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Strip {
     pub title: String,
     pub url: String,
+    /// Position in the archive, `0` being the oldest strip
     pub idx: usize,
     strip_type: StripType,
     pub site: Sites,
+    /// Alternative text of the image
+    #[serde(default)]
+    pub alt_text: Option<String>,
+    /// Text shown when hovering the image, where some sites hide an extra joke
+    #[serde(default)]
+    pub hover_text: Option<String>,
+    /// Day the strip was published, when the site tells
+    #[serde(default)]
+    pub published: Option<NaiveDate>,
+    /// Images following `url`, for strips published as several panels or pages
+    #[serde(default)]
+    pub extra_urls: Vec<String>,
 }
 
 impl Strip {
+    /// Strip made of the single image at `url`, the optional fields can be filled afterwards
+    pub fn new(site: Sites, title: String, url: String, idx: usize, strip_type: StripType) -> Self {
+        Strip {
+            title,
+            url,
+            idx,
+            strip_type,
+            site,
+            alt_text: None,
+            hover_text: None,
+            published: None,
+            extra_urls: Vec::new(),
+        }
+    }
+
+    pub fn with_alt_text(mut self, alt_text: Option<String>) -> Self {
+        self.alt_text = alt_text;
+        self
+    }
+
+    pub fn with_hover_text(mut self, hover_text: Option<String>) -> Self {
+        self.hover_text = hover_text;
+        self
+    }
+
+    pub fn with_published(mut self, published: Option<NaiveDate>) -> Self {
+        self.published = published;
+        self
+    }
+
+    pub fn with_extra_urls(mut self, extra_urls: Vec<String>) -> Self {
+        self.extra_urls = extra_urls;
+        self
+    }
+
     pub fn has_next(&self) -> bool {
         self.strip_type != StripType::First && self.strip_type != StripType::Unique
     }
@@ -110,16 +318,70 @@ impl Strip {
         self.strip_type == StripType::First || self.strip_type == StripType::Unique
     }
 
+    /// Every image of the strip, in reading order
+    pub fn image_urls(&self) -> Vec<&str> {
+        std::iter::once(self.url.as_str())
+            .chain(self.extra_urls.iter().map(String::as_str))
+            .collect()
+    }
+
     pub fn file_name(&self) -> String {
         let ext = self.url.split('.').rev().take(1).next().unwrap_or_default();
         format!("{}.{ext}", self.title)
     }
 }
 
-#[derive(Error, Debug)]
+/// Why a strip could not be fetched, fine grained enough for the frontends to explain it
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
 pub enum FetcherErrors {
-    #[error("Failed to fetch items")]
-    Error404,
+    #[error("Network error: {0}")]
+    Network(String),
+    #[error("The site answered with HTTP status {0}")]
+    HttpStatus(u16),
+    /// The selector or meta property that no longer matches anything
+    #[error("The site layout changed, nothing matches `{0}`")]
+    LayoutChanged(String),
+    #[error("Unreadable content: {0}")]
+    Parse(String),
+    /// Reading the local mirror or the caches failed
+    #[error("Storage error: {0}")]
+    Storage(String),
+    /// Neither the site nor a mirror of it could be loaded
+    #[error("The archive could not be loaded")]
+    Unavailable,
+    #[error("The archive is empty")]
+    EmptyArchive,
+    #[error("No such strip in the archive")]
+    OutOfRange,
+}
+
+impl FetcherErrors {
+    pub(crate) fn layout_changed(selector: &str) -> Self {
+        FetcherErrors::LayoutChanged(selector.to_owned())
+    }
+}
+
+impl From<anyhow::Error> for FetcherErrors {
+    /// Recover the variant bailed with, or classify the underlying error. The whole chain is
+    /// searched, errors wrapped with some context or as the source of another keep their kind
+    fn from(err: anyhow::Error) -> Self {
+        err.chain()
+            .find_map(|cause| {
+                if let Some(cause) = cause.downcast_ref::<FetcherErrors>() {
+                    return Some(cause.clone());
+                }
+                if let Some(cause) = cause.downcast_ref::<reqwest::Error>() {
+                    return Some(match cause.status() {
+                        Some(status) => FetcherErrors::HttpStatus(status.as_u16()),
+                        None if cause.is_decode() => FetcherErrors::Parse(cause.to_string()),
+                        None => FetcherErrors::Network(cause.to_string()),
+                    });
+                }
+                let cause = cause.downcast_ref::<std::io::Error>()?;
+                Some(FetcherErrors::Storage(cause.to_string()))
+            })
+            .unwrap_or_else(|| FetcherErrors::Parse(format!("{err:#}")))
+    }
 }
 
 pub trait Url {
@@ -142,8 +404,7 @@ impl Url for Sites {
             // Incomplete RSS feed. Switching to scraping
             Sites::DinosaurComics => "https://www.qwantz.com",
             Sites::Oglaf => "https://www.oglaf.com/feeds/rss",
-            // Incomplete RSS feed.
-            Sites::CadComics => "https://cad-comic.com/feed",
+            Sites::CadComics => "https://cad-comic.com/wp-json/wp/v2/posts",
             Sites::JoyOfTech => "https://www.joyoftech.com/joyoftech/jotblog",
             // Incomplete RSS feed.
             Sites::GoodTechThings => "https://www.goodtechthings.com/rss/",
@@ -158,9 +419,10 @@ impl Url for Sites {
             Sites::CatAndGirl => "https://catandgirl.com/archive",
             Sites::DieselSweeties1_0 => "https://www.dieselsweeties.com/archive",
             Sites::DieselSweeties3_0 => "https://www.dieselsweeties.com/ds-unifeed.xml",
-            Sites::PoorlyDrawnLines => "https://poorlydrawnlines.com/feed",
+            Sites::PoorlyDrawnLines => "https://poorlydrawnlines.com/wp-json/wp/v2/posts",
             Sites::PiledHigherAndDeeper => "https://phdcomics.com/comics/archive_list.php",
             Sites::GunnerkriggCourt => "https://www.gunnerkrigg.com/archives",
+            Sites::Custom(site) => site.fetch_url(),
         }
     }
 
@@ -190,14 +452,62 @@ impl Url for Sites {
             Sites::PoorlyDrawnLines => "poorlydrawnlines.com",
             Sites::PiledHigherAndDeeper => "phdcomics.com",
             Sites::GunnerkriggCourt => "gunnerkrigg.com",
+            Sites::Custom(site) => {
+                let homepage = &site.info().homepage;
+                homepage
+                    .split_once("://")
+                    .map_or(homepage.as_str(), |(_, rest)| rest)
+            }
         }
     }
 }
 
+/// Escape text for XML content and double quoted attributes
+pub(crate) fn escape_xml(value: &str) -> String {
+    value
+        .replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
 #[cfg(test)]
 mod test {
     use crate::{Fetcher, fetcher::build_fetcher};
 
+    #[test]
+    fn test_site_ids() {
+        for site in crate::Sites::all() {
+            assert_eq!(crate::Sites::from_id(site.id()), Some(site));
+            assert_eq!(site.to_string().parse::<crate::Sites>().unwrap(), site);
+        }
+    }
+
+    #[test]
+    fn test_sites_iter_builtin_only() {
+        use strum::IntoEnumIterator;
+
+        assert!(crate::Sites::iter().all(|site| !matches!(site, crate::Sites::Custom(_))));
+    }
+
+    #[test]
+    fn test_fetcher_errors_from_chain() {
+        use anyhow::{Context, anyhow};
+
+        use crate::FetcherErrors;
+
+        let bailed = anyhow!(FetcherErrors::OutOfRange).context("strip 3");
+        assert_eq!(FetcherErrors::from(bailed), FetcherErrors::OutOfRange);
+        let io = std::io::Error::new(std::io::ErrorKind::NotFound, "manifest.json");
+        let io = anyhow::Error::new(io).context("reading the mirror");
+        assert!(matches!(FetcherErrors::from(io), FetcherErrors::Storage(_)));
+        let unknown = "x".parse::<usize>().context("strip number").unwrap_err();
+        assert!(matches!(
+            FetcherErrors::from(unknown),
+            FetcherErrors::Parse(_)
+        ));
+    }
+
     #[tokio::test]
     async fn test_turnoff_us() {
         let fetcher = build_fetcher(crate::Sites::TurnoffUs).await;
@@ -1,17 +1,49 @@
 use anyhow::Result;
-use daily_strip::backend::start_backend;
-use daily_strip::frontend::Runnable;
+#[cfg(any(
+    feature = "egui_frontend",
+    feature = "slint_frontend",
+    feature = "tui_frontend"
+))]
+use daily_strip::{backend::start_backend, frontend::Runnable};
 
 fn main() -> Result<()> {
-    let (handle, tx, rx) = start_backend();
+    #[cfg(feature = "cli")]
+    {
+        use clap::Parser;
+        use daily_strip::cli::{Cli, run};
+
+        if let Some(command) = Cli::parse().command {
+            return run(command);
+        }
+    }
 
     #[cfg(feature = "egui_frontend")]
     {
+        let (handle, tx, rx) = start_backend();
         daily_strip::frontend::egui::EguiFrontend::run(handle, tx, rx)
     }
 
     #[cfg(all(feature = "slint_frontend", not(feature = "egui_frontend")))]
     {
+        let (handle, tx, rx) = start_backend();
         daily_strip::frontend::slint::SlintFrontend::run(handle, tx, rx)
     }
+
+    #[cfg(all(
+        feature = "tui_frontend",
+        not(any(feature = "egui_frontend", feature = "slint_frontend"))
+    ))]
+    {
+        let (handle, tx, rx) = start_backend();
+        daily_strip::frontend::tui::TuiFrontend::run(handle, tx, rx)
+    }
+
+    #[cfg(not(any(
+        feature = "egui_frontend",
+        feature = "slint_frontend",
+        feature = "tui_frontend"
+    )))]
+    {
+        anyhow::bail!("No graphical frontend enabled, see --help for the available commands")
+    }
 }
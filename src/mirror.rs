@@ -0,0 +1,338 @@
+use std::{
+    collections::HashSet,
+    path::{Path, PathBuf},
+    sync::Arc,
+    time::Duration,
+};
+
+use anyhow::Result;
+use chrono::NaiveDate;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use tokio::{
+    fs::{self, OpenOptions},
+    io::AsyncWriteExt,
+    sync::Semaphore,
+    task::JoinSet,
+    time::sleep,
+};
+
+use crate::{Fetcher, Sites, cache::ImageCache, export::image_extension};
+
+/// Strips resolved and downloaded at the same time by default
+pub const DEFAULT_MIRROR_CONCURRENCY: usize = 2;
+
+/// Pause after each strip by default, to be gentle with the origin
+pub const DEFAULT_MIRROR_DELAY: Duration = Duration::from_millis(500);
+
+/// One JSON entry per line, appended as strips are saved so that interrupted runs resume
+pub const MANIFEST: &str = "manifest.jsonl";
+
+/// Directory holding the mirrors, one sub directory per site id
+pub fn mirror_dir() -> PathBuf {
+    dirs::data_dir()
+        .unwrap_or_else(std::env::temp_dir)
+        .join("daily-strip")
+        .join("mirror")
+}
+
+/// Mirror of `site` in the default location
+pub fn site_mirror_dir(site: Sites) -> PathBuf {
+    mirror_dir().join(site.id())
+}
+
+/// Whether `site` has been mirrored, so that it can be browsed offline
+pub fn has_mirror(site: Sites) -> bool {
+    site_mirror_dir(site).join(MANIFEST).is_file()
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MirrorEntry {
+    pub idx: usize,
+    pub title: String,
+    /// Page the strip was found on
+    pub page: Option<String>,
+    /// Url the image was downloaded from
+    pub image: String,
+    /// Image file, relative to the mirror directory
+    pub file: String,
+    /// Hex encoded sha256 of the image
+    pub sha256: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub alt_text: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub hover_text: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub published: Option<NaiveDate>,
+    /// Files of the images following the first one, relative to the mirror directory
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub extra_files: Vec<String>,
+    /// Hex encoded sha256 of each of `extra_files`
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub extra_sha256: Vec<String>,
+}
+
+impl MirrorEntry {
+    /// Whether every file of the strip is in `dir` as it was downloaded
+    async fn is_intact(&self, dir: &Path) -> bool {
+        if self.extra_sha256.len() != self.extra_files.len() {
+            return false;
+        }
+        let files = std::iter::once((&self.file, &self.sha256))
+            .chain(self.extra_files.iter().zip(&self.extra_sha256));
+        for (file, sha256) in files {
+            match fs::read(dir.join(file)).await {
+                Ok(data) if sha256_hex(&data) == *sha256 => {}
+                _ => return false,
+            }
+        }
+        true
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MirrorOptions {
+    pub concurrency: usize,
+    pub delay: Duration,
+}
+
+impl Default for MirrorOptions {
+    fn default() -> Self {
+        Self {
+            concurrency: DEFAULT_MIRROR_CONCURRENCY,
+            delay: DEFAULT_MIRROR_DELAY,
+        }
+    }
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct MirrorReport {
+    pub total: usize,
+    /// Already in the mirror from a previous run
+    pub skipped: usize,
+    pub downloaded: usize,
+    /// Left out, they are tried again on the next run
+    pub failed: usize,
+}
+
+/// Entries of the manifest in `dir` sorted by `idx`
+pub async fn load_manifest(dir: &Path) -> Result<Vec<MirrorEntry>> {
+    Ok(parse_manifest(&read_manifest(dir).await?))
+}
+
+async fn read_manifest(dir: &Path) -> Result<String> {
+    match fs::read_to_string(dir.join(MANIFEST)).await {
+        Ok(content) => Ok(content),
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => Ok(String::new()),
+        Err(err) => Err(err.into()),
+    }
+}
+
+/// A line cut short by an interrupted run is ignored, later lines replace earlier ones
+fn parse_manifest(content: &str) -> Vec<MirrorEntry> {
+    let mut entries: Vec<MirrorEntry> = content
+        .lines()
+        .rev()
+        .filter_map(|line| serde_json::from_str(line).ok())
+        .collect();
+    entries.sort_by_key(|entry| entry.idx);
+    entries.dedup_by_key(|entry| entry.idx);
+    entries
+}
+
+/// Mirror the whole archive to `dir`, skipping the strips saved intact by previous runs.
+///
+/// `progress` is called with the strips done and the total after each one.
+pub async fn mirror<F>(
+    fetcher: Arc<F>,
+    dir: &Path,
+    options: MirrorOptions,
+    mut progress: impl FnMut(usize, usize),
+) -> Result<MirrorReport>
+where
+    F: Fetcher + Send + Sync + ?Sized + 'static,
+{
+    fs::create_dir_all(dir).await?;
+    let content = read_manifest(dir).await?;
+    let mut known = HashSet::new();
+    for entry in parse_manifest(&content) {
+        // Files cut short or altered since are downloaded again
+        if entry.is_intact(dir).await {
+            known.insert(entry.idx);
+        }
+    }
+
+    let total = match fetcher.strip_count() {
+        Some(count) => count,
+        None => fetcher.last().await?.idx + 1,
+    };
+    let mut report = MirrorReport {
+        total,
+        skipped: known.len().min(total),
+        ..Default::default()
+    };
+    progress(report.skipped, total);
+
+    // Images are written to the mirror only, keeping the shared cache for browsing
+    let images = Arc::new(ImageCache::new(0, None));
+    let permits = Arc::new(Semaphore::new(options.concurrency.max(1)));
+    let mut tasks = JoinSet::new();
+    for idx in (0..total).filter(|idx| !known.contains(idx)) {
+        let (fetcher, images, permits) = (fetcher.clone(), images.clone(), permits.clone());
+        let dir = dir.to_owned();
+        tasks.spawn(async move {
+            let _permit = permits.acquire_owned().await?;
+            let res = mirror_strip(fetcher.as_ref(), idx, &dir, &images).await;
+            sleep(options.delay).await;
+            res
+        });
+    }
+
+    let mut manifest = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(dir.join(MANIFEST))
+        .await?;
+    if !content.is_empty() && !content.ends_with('\n') {
+        manifest.write_all(b"\n").await?;
+    }
+    while let Some(res) = tasks.join_next().await {
+        match res? {
+            Ok(entry) => {
+                let line = serde_json::to_string(&entry)? + "\n";
+                manifest.write_all(line.as_bytes()).await?;
+                report.downloaded += 1;
+            }
+            Err(_) => report.failed += 1,
+        }
+        progress(report.skipped + report.downloaded + report.failed, total);
+    }
+    manifest.flush().await?;
+    Ok(report)
+}
+
+async fn mirror_strip<F: Fetcher + ?Sized>(
+    fetcher: &F,
+    idx: usize,
+    dir: &Path,
+    images: &ImageCache,
+) -> Result<MirrorEntry> {
+    let strip = fetcher.get(idx).await?;
+    let data = images.get(&strip.url).await?;
+    let file = format!("{idx:05}.{}", image_extension(&strip, &data));
+    fs::write(dir.join(&file), &data).await?;
+
+    let mut extra_files = Vec::with_capacity(strip.extra_urls.len());
+    let mut extra_sha256 = Vec::with_capacity(strip.extra_urls.len());
+    for (pos, url) in strip.extra_urls.iter().enumerate() {
+        let data = images.get(url).await?;
+        let file = format!("{idx:05}_{}.{}", pos + 2, image_extension(&strip, &data));
+        fs::write(dir.join(&file), &data).await?;
+        extra_files.push(file);
+        extra_sha256.push(sha256_hex(&data));
+    }
+
+    Ok(MirrorEntry {
+        idx,
+        title: strip.title,
+        page: fetcher.page_url(idx),
+        image: strip.url,
+        file,
+        sha256: sha256_hex(&data),
+        alt_text: strip.alt_text,
+        hover_text: strip.hover_text,
+        published: strip.published,
+        extra_files,
+        extra_sha256,
+    })
+}
+
+fn sha256_hex(data: &[u8]) -> String {
+    Sha256::digest(data)
+        .iter()
+        .map(|byte| format!("{byte:02x}"))
+        .collect()
+}
+
+#[cfg(test)]
+mod test {
+    use std::{sync::Arc, time::Duration};
+
+    use sha2::{Digest, Sha256};
+
+    use super::{MANIFEST, MirrorOptions, load_manifest, mirror};
+    use crate::{
+        Sites,
+        fetcher::{build_fetcher_with_base_url, fixture::FixtureServer},
+    };
+
+    #[tokio::test]
+    async fn test_mirror_resume() {
+        let routes = [
+            ("/archive.php", "archive.html"),
+            ("/index.php", "comic.html"),
+            ("/comics/*", "../images/strip.png"),
+        ];
+        let server = FixtureServer::start("dinosaur_comics", &routes).await;
+        let fetcher = build_fetcher_with_base_url(Sites::DinosaurComics, server.url())
+            .await
+            .map(Arc::new)
+            .unwrap();
+        let dir = std::env::temp_dir().join(format!("daily-strip-mirror-{}", std::process::id()));
+        let options = MirrorOptions {
+            concurrency: 2,
+            delay: Duration::ZERO,
+        };
+
+        let report = mirror(fetcher.clone(), &dir, options, |_, _| {})
+            .await
+            .unwrap();
+        assert_eq!((report.total, report.downloaded, report.failed), (2, 2, 0));
+        let entries = load_manifest(&dir).await.unwrap();
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[0].file, "00000.png");
+        assert!(entries[0].page.as_ref().unwrap().contains("comic=1"));
+        let image = std::fs::read(dir.join(&entries[0].file)).unwrap();
+        let expected = std::fs::read("tests/fixtures/images/strip.png").unwrap();
+        assert_eq!(image, expected);
+        assert_eq!(
+            entries[0].sha256,
+            format!("{:x}", Sha256::digest(&expected))
+        );
+
+        // Lose the last strip as if the run was interrupted, then resume
+        let manifest = std::fs::read_to_string(dir.join(MANIFEST)).unwrap();
+        let kept: Vec<_> = manifest
+            .lines()
+            .filter(|line| !line.contains("00001"))
+            .collect();
+        std::fs::write(dir.join(MANIFEST), kept.join("\n") + "\n{\"idx\":1,\"ti").unwrap();
+        let requests = server.requests().len();
+
+        let mut updates = Vec::new();
+        let report = mirror(fetcher.clone(), &dir, options, |done, total| {
+            updates.push((done, total))
+        })
+        .await
+        .unwrap();
+        assert_eq!((report.skipped, report.downloaded), (1, 1));
+        assert_eq!(updates, [(1, 2), (2, 2)]);
+        assert_eq!(load_manifest(&dir).await.unwrap().len(), 2);
+        // The page left to resolve and its image, the count comes from the index
+        assert_eq!(server.requests().len(), requests + 2);
+
+        // A file cut short is downloaded again
+        std::fs::write(dir.join("00000.png"), &expected[..10]).unwrap();
+        let report = mirror(fetcher, &dir, options, |_, _| {}).await.unwrap();
+        assert_eq!((report.skipped, report.downloaded), (1, 1));
+        assert_eq!(std::fs::read(dir.join("00000.png")).unwrap(), expected);
+        let entries = load_manifest(&dir).await.unwrap();
+        assert_eq!(entries.len(), 2);
+        assert_eq!(
+            entries[0].sha256,
+            format!("{:x}", Sha256::digest(&expected))
+        );
+
+        let _ = std::fs::remove_dir_all(dir);
+    }
+}
@@ -0,0 +1,368 @@
+use std::{
+    collections::HashMap,
+    net::SocketAddr,
+    sync::{Arc, Mutex},
+    time::Instant,
+};
+
+use anyhow::Result;
+use axum::{
+    Json, Router,
+    extract::{Path, Query, State},
+    http::{
+        HeaderValue, StatusCode,
+        header::{ACCESS_CONTROL_ALLOW_ORIGIN, CONTENT_TYPE},
+    },
+    middleware::map_response,
+    response::{Html, IntoResponse, Response},
+    routing::get,
+};
+use chrono::NaiveDate;
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+use tokio::net::TcpListener;
+
+use crate::{
+    FetcherErrors, Sites, Strip, Url,
+    cache::IndexCache,
+    feed::{DEFAULT_FEED_LENGTH, FeedFormat, merged_feed, recent_strips, site_feed},
+    fetcher::build_cached_fetcher,
+};
+
+type Fetcher = Arc<dyn crate::Fetcher + Send + Sync + 'static>;
+
+/// Minimal viewer served on `/`, `?site=<id>` preselects a site
+const INDEX: &str = include_str!("index.html");
+
+/// Strips per site a feed can be asked for, at most
+const MAX_FEED_LENGTH: usize = 50;
+
+/// Fetchers built on first use and shared by every request, along with when they were built
+#[derive(Clone, Default)]
+pub struct AppState {
+    fetchers: Arc<Mutex<HashMap<Sites, (Fetcher, Instant)>>>,
+    index_cache: Arc<IndexCache>,
+}
+
+impl AppState {
+    /// State keeping the archive indexes in `index_cache`, whose TTL also bounds how long a
+    /// fetcher is served before being refreshed
+    pub fn new(index_cache: IndexCache) -> Self {
+        Self {
+            fetchers: Arc::default(),
+            index_cache: Arc::new(index_cache),
+        }
+    }
+
+    async fn fetcher(&self, site: Sites) -> Result<Fetcher, ApiError> {
+        let built = self.fetchers.lock().unwrap().get(&site).cloned();
+        if let Some((fetcher, at)) = built.as_ref()
+            && at.elapsed() < self.index_cache.ttl()
+        {
+            return Ok(fetcher.clone());
+        }
+
+        // Built without holding the lock, loading an archive can take a while. Expired indexes
+        // are refreshed through the cache
+        let fetcher = match build_cached_fetcher(site, &self.index_cache).await {
+            Ok(fetcher) => fetcher,
+            Err(err) => {
+                return built
+                    .map(|(fetcher, _)| fetcher)
+                    .ok_or(ApiError::Unavailable(site, err));
+            }
+        };
+        let fetcher = Arc::new(fetcher) as Fetcher;
+        self.fetchers
+            .lock()
+            .unwrap()
+            .insert(site, (fetcher.clone(), Instant::now()));
+        Ok(fetcher)
+    }
+
+    #[cfg(test)]
+    fn insert(&self, site: Sites, fetcher: Fetcher) {
+        self.fetchers
+            .lock()
+            .unwrap()
+            .insert(site, (fetcher, Instant::now()));
+    }
+}
+
+#[derive(Debug, Error)]
+enum ApiError {
+    #[error("unknown site `{0}`")]
+    UnknownSite(String),
+    #[error("no site given, use `?sites=<id>,<id>`")]
+    NoSites,
+    #[error("failed to load the archive of {0}: {1}")]
+    Unavailable(Sites, FetcherErrors),
+    #[error("{0}")]
+    Strip(#[from] FetcherErrors),
+}
+
+impl From<anyhow::Error> for ApiError {
+    fn from(err: anyhow::Error) -> Self {
+        ApiError::Strip(err.into())
+    }
+}
+
+impl IntoResponse for ApiError {
+    fn into_response(self) -> Response {
+        let status = match self {
+            ApiError::UnknownSite(_)
+            | ApiError::Strip(FetcherErrors::OutOfRange | FetcherErrors::EmptyArchive) => {
+                StatusCode::NOT_FOUND
+            }
+            ApiError::NoSites => StatusCode::BAD_REQUEST,
+            ApiError::Unavailable(..) | ApiError::Strip(_) => StatusCode::BAD_GATEWAY,
+        };
+        let body = serde_json::json!({ "error": self.to_string() });
+        (status, Json(body)).into_response()
+    }
+}
+
+/// Query of the feed endpoints, `sites` is a comma separated list of ids
+#[derive(Deserialize)]
+struct FeedQuery {
+    #[serde(default)]
+    format: FeedFormat,
+    count: Option<usize>,
+    sites: Option<String>,
+}
+
+impl FeedQuery {
+    fn count(&self) -> usize {
+        self.count
+            .unwrap_or(DEFAULT_FEED_LENGTH)
+            .min(MAX_FEED_LENGTH)
+    }
+}
+
+#[derive(Serialize)]
+struct SiteInfo {
+    id: &'static str,
+    name: String,
+    homepage: String,
+    nsfw: bool,
+}
+
+pub fn router(state: AppState) -> Router {
+    Router::new()
+        .route("/", get(Html(INDEX)))
+        .route("/sites", get(sites))
+        .route("/feed", get(merged))
+        .route("/sites/{site}/last", get(last))
+        .route("/sites/{site}/random", get(random))
+        .route("/sites/{site}/feed", get(feed))
+        .route("/sites/{site}/date/{date}", get(by_date))
+        .route("/sites/{site}/{idx}", get(strip))
+        .layer(map_response(allow_any_origin))
+        .with_state(state)
+}
+
+/// Serve the API and the viewer on `addr` until the process is stopped
+pub async fn serve(addr: SocketAddr) -> Result<()> {
+    let listener = TcpListener::bind(addr).await?;
+    println!("Listening on http://{}", listener.local_addr()?);
+    axum::serve(listener, router(AppState::default())).await?;
+    Ok(())
+}
+
+/// Let dashboards on other origins query the API
+async fn allow_any_origin(mut res: Response) -> Response {
+    res.headers_mut()
+        .insert(ACCESS_CONTROL_ALLOW_ORIGIN, HeaderValue::from_static("*"));
+    res
+}
+
+fn parse_site(id: &str) -> Result<Sites, ApiError> {
+    Sites::from_id(id).ok_or_else(|| ApiError::UnknownSite(id.to_owned()))
+}
+
+async fn sites() -> Json<Vec<SiteInfo>> {
+    let sites = Sites::sites_sorted()
+        .into_iter()
+        .map(|site| SiteInfo {
+            id: site.id(),
+            name: site.to_string(),
+            homepage: format!("https://{}", site.homepage()),
+            nsfw: site.is_nsfw(),
+        })
+        .collect();
+    Json(sites)
+}
+
+async fn last(
+    State(state): State<AppState>,
+    Path(site): Path<String>,
+) -> Result<Json<Strip>, ApiError> {
+    let fetcher = state.fetcher(parse_site(&site)?).await?;
+    Ok(Json(fetcher.last().await?))
+}
+
+async fn random(
+    State(state): State<AppState>,
+    Path(site): Path<String>,
+) -> Result<Json<Strip>, ApiError> {
+    let fetcher = state.fetcher(parse_site(&site)?).await?;
+    Ok(Json(fetcher.random().await?))
+}
+
+async fn strip(
+    State(state): State<AppState>,
+    Path((site, idx)): Path<(String, usize)>,
+) -> Result<Json<Strip>, ApiError> {
+    let fetcher = state.fetcher(parse_site(&site)?).await?;
+    Ok(Json(fetcher.get(idx).await?))
+}
+
+async fn by_date(
+    State(state): State<AppState>,
+    Path((site, date)): Path<(String, NaiveDate)>,
+) -> Result<Json<Strip>, ApiError> {
+    let fetcher = state.fetcher(parse_site(&site)?).await?;
+    Ok(Json(fetcher.by_date(date).await?))
+}
+
+async fn feed(
+    State(state): State<AppState>,
+    Path(site): Path<String>,
+    Query(query): Query<FeedQuery>,
+) -> Result<impl IntoResponse, ApiError> {
+    let site = parse_site(&site)?;
+    let fetcher = state.fetcher(site).await?;
+    let strips = recent_strips(fetcher.as_ref(), query.count()).await?;
+    let feed = site_feed(site, &strips, query.format);
+    Ok(([(CONTENT_TYPE, query.format.content_type())], feed))
+}
+
+async fn merged(
+    State(state): State<AppState>,
+    Query(query): Query<FeedQuery>,
+) -> Result<impl IntoResponse, ApiError> {
+    let sites = query
+        .sites
+        .as_deref()
+        .unwrap_or_default()
+        .split(',')
+        .filter(|id| !id.is_empty())
+        .map(parse_site)
+        .collect::<Result<Vec<_>, _>>()?;
+    if sites.is_empty() {
+        return Err(ApiError::NoSites);
+    }
+
+    // Sites failing to load are left out rather than failing the whole feed
+    let mut strips = Vec::with_capacity(sites.len());
+    for site in sites {
+        if let Ok(fetcher) = state.fetcher(site).await
+            && let Ok(recent) = recent_strips(fetcher.as_ref(), query.count()).await
+        {
+            strips.push(recent);
+        }
+    }
+    let feed = merged_feed(&strips, query.format);
+    Ok(([(CONTENT_TYPE, query.format.content_type())], feed))
+}
+
+#[cfg(test)]
+mod test {
+    use std::{path::Path, sync::Arc, time::Duration};
+
+    use tokio::{net::TcpListener, spawn};
+
+    use super::{AppState, router};
+    use crate::{
+        Sites, Strip,
+        cache::IndexCache,
+        custom::{SiteDefinition, register},
+        fetcher::{build_fetcher_with_base_url, fixture::FixtureServer},
+    };
+
+    #[tokio::test]
+    async fn test_server_api() {
+        let routes = [("/", "index.html"), ("/*", "comic.html")];
+        let fixture = FixtureServer::start("xkcd", &routes).await;
+        let fetcher = build_fetcher_with_base_url(Sites::Xkcd, fixture.url())
+            .await
+            .unwrap();
+        let state = AppState::default();
+        state.insert(Sites::Xkcd, Arc::new(fetcher));
+
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let base = format!("http://{}", listener.local_addr().unwrap());
+        let server = spawn(async move { axum::serve(listener, router(state)).await });
+
+        let get = async |path: &str| {
+            let res = reqwest::get(format!("{base}{path}")).await.unwrap();
+            (res.status().as_u16(), res.text().await.unwrap())
+        };
+
+        let (status, body) = get("/sites").await;
+        assert_eq!(status, 200);
+        assert!(body.contains(r#""id":"xkcd""#));
+        assert!(body.contains(
+            r#""id":"oglaf","name":"Oglaf [NSFW]","homepage":"https://oglaf.com","nsfw":true"#
+        ));
+
+        let (status, body) = get("/sites/xkcd/last").await;
+        assert_eq!(status, 200);
+        let last: Strip = serde_json::from_str(&body).unwrap();
+        assert_eq!(last.title, "3");
+        assert!(last.is_last());
+
+        let (status, body) = get(&format!("/sites/xkcd/{}", last.idx - 1)).await;
+        assert_eq!(status, 200);
+        assert_eq!(serde_json::from_str::<Strip>(&body).unwrap().title, "2");
+
+        assert_eq!(get("/sites/xkcd/random").await.0, 200);
+        let (status, body) = get("/sites/xkcd/999").await;
+        assert_eq!(status, 404);
+        assert!(body.contains("No such strip"));
+        assert_eq!(get("/sites/nope/last").await.0, 404);
+        // xkcd pages carry no date
+        assert_eq!(get("/sites/xkcd/date/2024-01-01").await.0, 404);
+        assert_eq!(get("/sites/xkcd/date/yesterday").await.0, 400);
+        assert!(get("/").await.1.contains("<title>Daily Strip</title>"));
+
+        let (status, body) = get("/sites/xkcd/feed?format=atom&count=2").await;
+        assert_eq!(status, 200);
+        assert_eq!(body.matches("<entry>").count(), 2);
+        let (status, body) = get("/feed?sites=xkcd").await;
+        assert_eq!(status, 200);
+        assert!(body.contains("<rss"));
+        assert_eq!(get("/feed").await.0, 400);
+
+        server.abort();
+    }
+
+    #[tokio::test]
+    async fn test_server_refresh() {
+        let routes = [("/archive/", "archive.html"), ("/strips/*", "strip.html")];
+        let fixture = FixtureServer::start("generic", &routes).await;
+        let path = Path::new("tests/fixtures/generic/site.toml");
+        let mut definition = SiteDefinition::load(path).unwrap();
+        definition.info.id = "served-comic".to_owned();
+        definition.info.name = "Served Comic".to_owned();
+        definition.index_url = format!("{}/archive/", fixture.url());
+        let site = register(definition).unwrap();
+
+        let dir = std::env::temp_dir().join(format!("daily-strip-serve-{}", std::process::id()));
+        let archive_requests = || {
+            let requests = fixture.requests();
+            requests.iter().filter(|path| *path == "/archive/").count()
+        };
+        for (ttl, requests) in [(Duration::from_secs(60), 1), (Duration::ZERO, 2)] {
+            let state = AppState::new(IndexCache::new(dir.clone(), ttl));
+            let before = archive_requests();
+            for _ in 0..2 {
+                let fetcher = state.fetcher(site).await.unwrap();
+                assert_eq!(fetcher.last().await.unwrap().title, "Newest");
+            }
+            // Expired indexes are revalidated on each request, fresh ones are served as they are
+            assert_eq!(archive_requests() - before, requests);
+            let _ = std::fs::remove_dir_all(&dir);
+        }
+    }
+}
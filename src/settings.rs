@@ -0,0 +1,84 @@
+use std::path::{Path, PathBuf};
+
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+
+use crate::{custom, http::HttpSettings};
+
+/// User settings, read from `settings.toml` in the config directory.
+///
+/// Every key is optional, missing ones keep their default:
+///
+/// ```toml
+/// [http]
+/// timeout = 30
+/// retries = 2
+/// user_agent = "my-reader/1.0"
+/// proxy = "http://localhost:3128"
+/// host_interval = 250
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Settings {
+    pub http: HttpSettings,
+}
+
+impl Settings {
+    /// Settings from the default file, the defaults when there is none
+    pub fn load() -> Result<Self> {
+        Self::load_from(&settings_path())
+    }
+
+    pub fn load_from(path: &Path) -> Result<Self> {
+        match std::fs::read_to_string(path) {
+            Ok(data) => Ok(toml::from_str(&data)?),
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => Ok(Self::default()),
+            Err(err) => Err(err.into()),
+        }
+    }
+}
+
+/// Directory holding the configuration files of the app
+pub fn config_dir() -> PathBuf {
+    dirs::config_dir()
+        .unwrap_or_else(std::env::temp_dir)
+        .join("daily-strip")
+}
+
+pub fn settings_path() -> PathBuf {
+    config_dir().join("settings.toml")
+}
+
+/// Configuration files ignored for being broken, and why, for the frontends to report
+pub fn config_errors() -> Vec<String> {
+    let mut errors = Vec::new();
+    if let Err(err) = Settings::load() {
+        errors.push(format!("{}: {err}", settings_path().display()));
+    }
+    errors.extend(custom::skipped().iter().cloned());
+    errors
+}
+
+#[cfg(test)]
+mod test {
+    use std::fs;
+
+    use super::Settings;
+
+    #[test]
+    fn test_settings_load() {
+        let path =
+            std::env::temp_dir().join(format!("daily-strip-settings-{}.toml", std::process::id()));
+        assert_eq!(Settings::load_from(&path).unwrap(), Settings::default());
+
+        fs::write(&path, "[http]\nretries = 5\nuser_agent = \"test\"\n").unwrap();
+        let settings = Settings::load_from(&path).unwrap();
+        assert_eq!(settings.http.retries, 5);
+        assert_eq!(settings.http.user_agent, "test");
+        assert_eq!(settings.http.timeout, Settings::default().http.timeout);
+
+        fs::write(&path, "[http]\nretries = \"many\"\n").unwrap();
+        assert!(Settings::load_from(&path).is_err());
+        let _ = fs::remove_file(path);
+    }
+}
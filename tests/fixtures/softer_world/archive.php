@@ -0,0 +1,5 @@
+<html><body><table>
+<tr><td><a href="{{base}}/index.php">Home</a></td></tr>
+<tr><td><a href="{{base}}/index.php?id=1">1</a></td></tr>
+<tr><td><a href="{{base}}/index.php?id=2">2</a></td></tr>
+</table></body></html>
@@ -0,0 +1,99 @@
+use anyhow::{Result, bail};
+use async_trait::async_trait;
+use chrono::NaiveDate;
+use daily_strip::{
+    Fetcher, FetcherErrors, Sites, Strip, StripType,
+    custom::{SiteInfo, register_fetcher},
+    fetcher::build_fetcher,
+};
+
+/// Fetcher of another crate, built only from what daily_strip exports
+struct Pages {
+    site: Sites,
+    titles: Vec<&'static str>,
+}
+
+impl Pages {
+    fn strip(&self, idx: usize) -> Result<Strip> {
+        let Some(title) = self.titles.get(idx) else {
+            bail!(FetcherErrors::OutOfRange)
+        };
+        let url = format!("https://pages.example/{idx}.png");
+        let strip_type = StripType::at(idx, self.titles.len());
+        let mut strip = Strip::new(self.site, title.to_string(), url, idx, strip_type);
+        strip.published = NaiveDate::from_ymd_opt(2024, 1, idx as u32 + 1);
+        Ok(strip)
+    }
+}
+
+#[async_trait]
+impl Fetcher for Pages {
+    async fn reload(&mut self) -> Result<()> {
+        self.titles = vec!["Oldest", "Middle", "Newest"];
+        Ok(())
+    }
+
+    async fn refresh(&mut self) -> Result<usize> {
+        Ok(0)
+    }
+
+    async fn last(&self) -> Result<Strip> {
+        self.strip(self.titles.len().saturating_sub(1))
+    }
+
+    async fn random(&self) -> Result<Strip> {
+        self.strip(0)
+    }
+
+    async fn get(&self, idx: usize) -> Result<Strip> {
+        self.strip(idx)
+    }
+
+    async fn next(&self, idx: usize) -> Result<Strip> {
+        self.strip(idx + 1)
+    }
+
+    async fn prev(&self, idx: usize) -> Result<Strip> {
+        match idx {
+            0 => bail!(FetcherErrors::OutOfRange),
+            idx => self.strip(idx - 1),
+        }
+    }
+
+    async fn by_date(&self, _date: NaiveDate) -> Result<Strip> {
+        self.last().await
+    }
+
+    fn page_url(&self, idx: usize) -> Option<String> {
+        (idx < self.titles.len()).then(|| format!("https://pages.example/{idx}"))
+    }
+}
+
+#[tokio::test]
+async fn test_register_external_fetcher() {
+    let info = SiteInfo {
+        id: "external-pages".to_owned(),
+        name: "External Pages".to_owned(),
+        homepage: "https://pages.example".to_owned(),
+        nsfw: false,
+    };
+    let site = register_fetcher(info, |site| {
+        Box::new(Pages {
+            site,
+            titles: Vec::new(),
+        })
+    })
+    .unwrap();
+    assert!(Sites::all().contains(&site));
+
+    // Reloaded once built
+    let fetcher = build_fetcher(site).await.unwrap();
+    let last = fetcher.last().await.unwrap();
+    assert_eq!((last.title.as_str(), last.site), ("Newest", site));
+    assert!(last.is_last() && last.has_prev() && !last.has_next());
+
+    let oldest = fetcher.prev(1).await.unwrap();
+    assert!(oldest.has_next() && !oldest.has_prev());
+    assert_eq!(oldest.published, NaiveDate::from_ymd_opt(2024, 1, 1));
+    assert!(fetcher.next(2).await.is_err());
+}